@@ -0,0 +1,225 @@
+use std::cell::Cell;
+
+use crate::{HashMap, KeySet, KeySetOf, StorageThresholds, StorageType, StoreKey, TypeKey};
+
+/// Key density at or above which direct-indexed storage wastes little memory on holes
+const DENSE_KEY_DENSITY: f32 = 0.5;
+
+/// Key density below which a sparse index costs more than hashing
+const SPARSE_KEY_DENSITY: f32 = 1.0 / 16.0;
+
+/// Inserts and removes per live key at or above which a type is considered high-churn
+const HIGH_CHURN: f32 = 1.0;
+
+/// Accesses a type must record since the last reset before its storage is re-evaluated
+pub const MIN_STORAGE_SAMPLES: usize = 16;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum StorageAccess {
+    Insert,
+    Remove,
+    Random,
+    Iterated,
+}
+
+/// Access counters for a single type, gathered between storage re-evaluations
+#[derive(Debug, Default)]
+pub struct StorageStatistics {
+    size: usize,
+    inserts: Cell<usize>,
+    removes: Cell<usize>,
+    random_accesses: Cell<usize>,
+    iterated_accesses: Cell<usize>,
+}
+
+impl StorageStatistics {
+    pub fn new(size: usize) -> Self {
+        StorageStatistics {
+            size,
+            ..Default::default()
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn inserts(&self) -> usize {
+        self.inserts.get()
+    }
+
+    pub fn removes(&self) -> usize {
+        self.removes.get()
+    }
+
+    pub fn random_accesses(&self) -> usize {
+        self.random_accesses.get()
+    }
+
+    pub fn iterated_accesses(&self) -> usize {
+        self.iterated_accesses.get()
+    }
+
+    /// Accesses of every kind recorded since the last reset
+    pub fn accesses(&self) -> usize {
+        self.inserts() + self.removes() + self.random_accesses() + self.iterated_accesses()
+    }
+
+    pub fn record(&self, access: StorageAccess) {
+        let counter = match access {
+            StorageAccess::Insert => &self.inserts,
+            StorageAccess::Remove => &self.removes,
+            StorageAccess::Random => &self.random_accesses,
+            StorageAccess::Iterated => &self.iterated_accesses,
+        };
+
+        counter.set(counter.get() + 1);
+    }
+
+    pub fn reset(&self) {
+        self.inserts.set(0);
+        self.removes.set(0);
+        self.random_accesses.set(0);
+        self.iterated_accesses.set(0);
+    }
+
+    /// Pick the best storage for a type given its current keys and the accesses recorded since the last reset
+    pub fn select_storage_type<Key>(
+        &self,
+        keys: &KeySetOf<Key>,
+        storage_thresholds: &StorageThresholds,
+    ) -> StorageType
+    where
+        Key: StoreKey,
+    {
+        if self.size == 0 {
+            return StorageType::Unit;
+        }
+
+        let (len, max_key) = keys
+            .iter()
+            .fold((0, 0), |(len, _), key| (len + 1, key.to_index()));
+
+        let density = len as f32 / (max_key as f32 + 1.0);
+        let churn = (self.inserts() + self.removes()) as f32 / len.max(1) as f32;
+        let iteration_heavy =
+            self.iterated_accesses() > 0 && self.iterated_accesses() >= self.random_accesses();
+        let fits_dense = self.size <= storage_thresholds.dense_vec_map;
+
+        if density >= DENSE_KEY_DENSITY && self.size < storage_thresholds.sparse_vec_map {
+            StorageType::SparseVecMap
+        } else if (density >= DENSE_KEY_DENSITY || iteration_heavy) && fits_dense {
+            StorageType::DenseVecMap
+        } else if density < SPARSE_KEY_DENSITY || churn >= HIGH_CHURN || !fits_dense {
+            StorageType::HashMap
+        } else {
+            StorageType::DenseVecMap
+        }
+    }
+}
+
+/// Opt-in per-type statistics used to periodically move types to better-suited storage
+#[derive(Debug, Default)]
+pub struct AdaptiveStorage {
+    interval: usize,
+    mutations: usize,
+    statistics: HashMap<TypeKey, StorageStatistics>,
+}
+
+impl AdaptiveStorage {
+    /// Re-evaluate storage every `interval` inserts and removes
+    pub fn new(interval: usize) -> Self {
+        AdaptiveStorage {
+            interval,
+            ..Default::default()
+        }
+    }
+
+    pub fn interval(&self) -> usize {
+        self.interval
+    }
+
+    pub fn statistics_for(&self, type_key: &TypeKey) -> Option<&StorageStatistics> {
+        self.statistics.get(type_key)
+    }
+
+    pub fn iter_statistics(&self) -> impl Iterator<Item = (&TypeKey, &StorageStatistics)> {
+        self.statistics.iter()
+    }
+
+    pub fn register(&mut self, type_key: TypeKey, size: usize) {
+        self.statistics
+            .entry(type_key)
+            .or_insert_with(|| StorageStatistics::new(size));
+    }
+
+    pub fn record(&self, type_key: &TypeKey, access: StorageAccess) {
+        if let Some(statistics) = self.statistics.get(type_key) {
+            statistics.record(access);
+        }
+    }
+
+    /// Count a mutation, returning true when a re-evaluation is due
+    pub fn tick(&mut self) -> bool {
+        self.mutations += 1;
+        if self.interval > 0 && self.mutations >= self.interval {
+            self.mutations = 0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hibitset::BitSet;
+
+    fn bit_set(keys: &[u32]) -> BitSet {
+        let mut bit_set = BitSet::new();
+        for key in keys {
+            bit_set.add(*key);
+        }
+        bit_set
+    }
+
+    #[test]
+    fn select_storage_type() {
+        let storage_thresholds = StorageThresholds::default();
+        let dense_keys = bit_set(&(0..64).collect::<Vec<_>>());
+        let sparse_keys = bit_set(&[0, 1000, 2000, 3000]);
+
+        let statistics = StorageStatistics::new(0);
+        assert_eq!(
+            statistics.select_storage_type::<u32>(&sparse_keys, &storage_thresholds),
+            StorageType::Unit
+        );
+
+        let statistics = StorageStatistics::new(1);
+        assert_eq!(
+            statistics.select_storage_type::<u32>(&dense_keys, &storage_thresholds),
+            StorageType::SparseVecMap
+        );
+
+        // Without recorded iteration, sparse keys are not treated as iteration-heavy
+        let statistics = StorageStatistics::new(64);
+        assert_eq!(
+            statistics.select_storage_type::<u32>(&sparse_keys, &storage_thresholds),
+            StorageType::HashMap
+        );
+
+        statistics.record(StorageAccess::Iterated);
+        assert_eq!(
+            statistics.select_storage_type::<u32>(&sparse_keys, &storage_thresholds),
+            StorageType::DenseVecMap
+        );
+
+        statistics.reset();
+        statistics.record(StorageAccess::Random);
+        assert_eq!(
+            statistics.select_storage_type::<u32>(&sparse_keys, &storage_thresholds),
+            StorageType::HashMap
+        );
+    }
+}
@@ -0,0 +1,447 @@
+use std::{
+    any::Any,
+    cell::{Ref, RefCell, RefMut},
+    fmt::Debug,
+};
+
+use crate::{key_map::KeyMap, KeySet, KeySetOf, StoreKey, TypeKey, TypedData};
+
+/// Type-erased column of values for a single type within an Archetype
+trait Column: Debug {
+    fn as_any(&self) -> &dyn Any;
+    fn empty(&self) -> Box<dyn Column>;
+    fn len(&self) -> usize;
+    fn get_untyped(&self, row: usize) -> Ref<'_, dyn TypedData>;
+    /// Swap-remove a row, pushing its value onto the end of another column of the same type
+    fn move_row(&self, row: usize, dest: &dyn Column);
+    fn remove_row(&self, row: usize);
+}
+
+#[derive(Debug)]
+struct TypedColumn<T>(RefCell<Vec<T>>);
+
+impl<T> TypedColumn<T> {
+    fn new() -> Self {
+        TypedColumn(RefCell::new(Vec::new()))
+    }
+}
+
+impl<T> Column for TypedColumn<T>
+where
+    T: Debug + 'static,
+{
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn empty(&self) -> Box<dyn Column> {
+        Box::new(TypedColumn::<T>::new())
+    }
+
+    fn len(&self) -> usize {
+        self.0.borrow().len()
+    }
+
+    fn get_untyped(&self, row: usize) -> Ref<'_, dyn TypedData> {
+        Ref::map(self.0.borrow(), |values| &values[row] as &dyn TypedData)
+    }
+
+    fn move_row(&self, row: usize, dest: &dyn Column) {
+        let value = self.0.borrow_mut().swap_remove(row);
+        typed::<T>(dest).0.borrow_mut().push(value);
+    }
+
+    fn remove_row(&self, row: usize) {
+        self.0.borrow_mut().swap_remove(row);
+    }
+}
+
+fn typed<T>(column: &dyn Column) -> &TypedColumn<T>
+where
+    T: 'static,
+{
+    Column::as_any(column)
+        .downcast_ref::<TypedColumn<T>>()
+        .expect("Column type mismatch")
+}
+
+/// Table of every key holding exactly one set of types, with a column per type
+#[derive(Debug)]
+struct Archetype<Key> {
+    types: Vec<TypeKey>,
+    keys: Vec<Key>,
+    columns: Vec<Box<dyn Column>>,
+}
+
+impl<Key> Archetype<Key> {
+    fn column(&self, type_key: &TypeKey) -> Option<&dyn Column> {
+        let index = self.types.binary_search(type_key).ok()?;
+        Some(&*self.columns[index])
+    }
+
+    fn contains_type(&self, type_key: &TypeKey) -> bool {
+        self.types.binary_search(type_key).is_ok()
+    }
+}
+
+/// Keys grouped by their exact set of types into column tables, used by Store in archetype mode
+#[derive(Debug)]
+pub(crate) struct Archetypes<Key>
+where
+    Key: StoreKey,
+{
+    archetypes: Vec<Archetype<Key>>,
+    locations: KeyMap<Key, (u32, u32)>,
+}
+
+impl<Key> Default for Archetypes<Key>
+where
+    Key: StoreKey,
+{
+    fn default() -> Self {
+        Archetypes {
+            archetypes: Vec::new(),
+            locations: KeyMap::default(),
+        }
+    }
+}
+
+impl<Key> Archetypes<Key>
+where
+    Key: StoreKey,
+{
+    fn location(&self, key: &Key) -> Option<(usize, usize)> {
+        let (archetype, row) = self.locations.get(key)?;
+        Some((*archetype as usize, *row as usize))
+    }
+
+    fn column_of(&self, key: &Key, type_key: &TypeKey) -> Option<(&dyn Column, usize)> {
+        let (archetype, row) = self.location(key)?;
+        Some((self.archetypes[archetype].column(type_key)?, row))
+    }
+
+    pub fn get<T>(&self, key: &Key) -> Option<Ref<'_, T>>
+    where
+        T: 'static,
+    {
+        let (column, row) = self.column_of(key, &TypeKey::of::<T>())?;
+        Some(Ref::map(typed::<T>(column).0.borrow(), |values| {
+            &values[row]
+        }))
+    }
+
+    pub fn get_mut<T>(&self, key: &Key) -> Option<RefMut<'_, T>>
+    where
+        T: 'static,
+    {
+        let (column, row) = self.column_of(key, &TypeKey::of::<T>())?;
+        Some(RefMut::map(typed::<T>(column).0.borrow_mut(), |values| {
+            &mut values[row]
+        }))
+    }
+
+    pub fn insert<T>(&mut self, key: Key, value: T)
+    where
+        T: Debug + 'static,
+    {
+        let type_key = TypeKey::of::<T>();
+
+        let destination = match self.location(&key) {
+            Some((archetype, row)) => {
+                if let Some(column) = self.archetypes[archetype].column(&type_key) {
+                    typed::<T>(column).0.borrow_mut()[row] = value;
+                    return;
+                }
+
+                let mut types = self.archetypes[archetype].types.clone();
+                types.push(type_key);
+                types.sort();
+
+                let destination = self.archetype_for(types, |archetypes| {
+                    let source = &archetypes.archetypes[archetype];
+                    let mut columns: Vec<(TypeKey, Box<dyn Column>)> = source
+                        .types
+                        .iter()
+                        .copied()
+                        .zip(source.columns.iter().map(|column| column.empty()))
+                        .collect();
+                    columns.push((type_key, Box::new(TypedColumn::<T>::new())));
+                    columns
+                });
+                self.move_row(key, archetype, row, destination, None);
+                destination
+            }
+            None => {
+                let destination = self.archetype_for(vec![type_key], |_| {
+                    vec![(type_key, Box::new(TypedColumn::<T>::new()))]
+                });
+                self.archetypes[destination].keys.push(key);
+                let row = self.archetypes[destination].keys.len() - 1;
+                self.locations.insert(key, (destination as u32, row as u32));
+                destination
+            }
+        };
+
+        let column = self.archetypes[destination].column(&type_key).unwrap();
+        typed::<T>(column).0.borrow_mut().push(value);
+    }
+
+    /// Remove a key's value of the given type, returning whether it was present
+    pub fn remove(&mut self, key: &Key, type_key: &TypeKey) -> bool {
+        let (archetype, row) = match self.location(key) {
+            Some(location) => location,
+            None => return false,
+        };
+
+        if !self.archetypes[archetype].contains_type(type_key) {
+            return false;
+        }
+
+        let types: Vec<TypeKey> = self.archetypes[archetype]
+            .types
+            .iter()
+            .filter(|candidate| *candidate != type_key)
+            .copied()
+            .collect();
+
+        if types.is_empty() {
+            self.remove_key(key);
+            return true;
+        }
+
+        let destination = self.archetype_for(types, |archetypes| {
+            let archetype = &archetypes.archetypes[archetype];
+            archetype
+                .types
+                .iter()
+                .zip(archetype.columns.iter())
+                .filter(|(candidate, _)| *candidate != type_key)
+                .map(|(candidate, column)| (*candidate, column.empty()))
+                .collect()
+        });
+        self.move_row(*key, archetype, row, destination, Some(type_key));
+
+        true
+    }
+
+    /// Remove a key and all of its values, returning the types it held
+    pub fn remove_key(&mut self, key: &Key) -> Vec<TypeKey> {
+        let (archetype, row) = match self.location(key) {
+            Some(location) => location,
+            None => return vec![],
+        };
+
+        for column in &self.archetypes[archetype].columns {
+            column.remove_row(row);
+        }
+        self.locations.remove(key);
+        self.swap_remove_key(archetype, row);
+
+        self.archetypes[archetype].types.clone()
+    }
+
+    pub fn contains_type(&self, type_key: &TypeKey) -> bool {
+        self.archetypes
+            .iter()
+            .any(|archetype| archetype.contains_type(type_key))
+    }
+
+    pub fn contains_key(&self, key: &Key) -> bool {
+        self.locations.contains_key(key)
+    }
+
+    pub fn types_of(&self, key: &Key) -> &[TypeKey] {
+        match self.location(key) {
+            Some((archetype, _)) => &self.archetypes[archetype].types,
+            None => &[],
+        }
+    }
+
+    pub fn contains_type_key(&self, key: &Key, type_key: &TypeKey) -> bool {
+        self.column_of(key, type_key).is_some()
+    }
+
+    pub fn keys(&self, type_key: &TypeKey) -> KeySetOf<Key> {
+        let mut key_set = KeySetOf::<Key>::default();
+        for archetype in &self.archetypes {
+            if archetype.contains_type(type_key) {
+                for key in &archetype.keys {
+                    key_set.insert(key);
+                }
+            }
+        }
+        key_set
+    }
+
+    pub fn keys_all(&self) -> KeySetOf<Key> {
+        let mut key_set = KeySetOf::<Key>::default();
+        for key in self.locations.keys() {
+            key_set.insert(&key);
+        }
+        key_set
+    }
+
+    /// Keys of every table holding all of `required` and none of `excluded`, in table order
+    pub fn query_keys(&self, required: &[TypeKey], excluded: &[TypeKey]) -> Vec<Key> {
+        self.archetypes
+            .iter()
+            .filter(|archetype| {
+                required
+                    .iter()
+                    .all(|type_key| archetype.contains_type(type_key))
+                    && !excluded
+                        .iter()
+                        .any(|type_key| archetype.contains_type(type_key))
+            })
+            .flat_map(|archetype| archetype.keys.iter().copied())
+            .collect()
+    }
+
+    /// Keys of every table holding all of `required` and none of `excluded`, with their table and row, in table order
+    pub fn query_rows(
+        &self,
+        required: &[TypeKey],
+        excluded: &[TypeKey],
+    ) -> Vec<(Key, usize, usize)> {
+        self.archetypes
+            .iter()
+            .enumerate()
+            .filter(|(_, archetype)| {
+                required
+                    .iter()
+                    .all(|type_key| archetype.contains_type(type_key))
+                    && !excluded
+                        .iter()
+                        .any(|type_key| archetype.contains_type(type_key))
+            })
+            .flat_map(|(table, archetype)| {
+                archetype
+                    .keys
+                    .iter()
+                    .enumerate()
+                    .map(move |(row, key)| (*key, table, row))
+            })
+            .collect()
+    }
+
+    /// Borrow the column of T in a table, or None if the table does not hold T
+    pub fn column<T>(&self, table: usize) -> Option<Ref<'_, [T]>>
+    where
+        T: 'static,
+    {
+        let column = self.archetypes[table].column(&TypeKey::of::<T>())?;
+        Some(Ref::map(typed::<T>(column).0.borrow(), Vec::as_slice))
+    }
+
+    pub fn column_mut<T>(&self, table: usize) -> Option<RefMut<'_, [T]>>
+    where
+        T: 'static,
+    {
+        let column = self.archetypes[table].column(&TypeKey::of::<T>())?;
+        Some(RefMut::map(
+            typed::<T>(column).0.borrow_mut(),
+            Vec::as_mut_slice,
+        ))
+    }
+
+    pub fn types(&self) -> Vec<&TypeKey> {
+        let mut types: Vec<&TypeKey> = self
+            .archetypes
+            .iter()
+            .flat_map(|archetype| archetype.types.iter())
+            .collect();
+        types.sort();
+        types.dedup();
+        types
+    }
+
+    /// Every value of every table, skipping the rows of `excluded` keys
+    pub fn iter_untyped(&self, excluded: &KeySetOf<Key>) -> Vec<(TypeKey, Ref<'_, dyn TypedData>)> {
+        let mut typed_data = vec![];
+        for archetype in &self.archetypes {
+            for (type_key, column) in archetype.types.iter().zip(archetype.columns.iter()) {
+                for row in 0..column.len() {
+                    if !excluded.contains(&archetype.keys[row]) {
+                        typed_data.push((*type_key, column.get_untyped(row)));
+                    }
+                }
+            }
+        }
+        typed_data
+    }
+
+    pub fn iter_key_untyped(&self, key: &Key) -> Vec<(TypeKey, Ref<'_, dyn TypedData>)> {
+        match self.location(key) {
+            Some((archetype, row)) => {
+                let archetype = &self.archetypes[archetype];
+                archetype
+                    .types
+                    .iter()
+                    .zip(archetype.columns.iter())
+                    .map(|(type_key, column)| (*type_key, column.get_untyped(row)))
+                    .collect()
+            }
+            None => vec![],
+        }
+    }
+
+    /// Find the table for a sorted set of types, creating it from the provided columns if it does not yet exist
+    fn archetype_for<F>(&mut self, types: Vec<TypeKey>, columns: F) -> usize
+    where
+        F: FnOnce(&Self) -> Vec<(TypeKey, Box<dyn Column>)>,
+    {
+        if let Some(index) = self
+            .archetypes
+            .iter()
+            .position(|archetype| archetype.types == types)
+        {
+            return index;
+        }
+
+        let mut columns = columns(self);
+        columns.sort_by_key(|(type_key, _)| *type_key);
+
+        self.archetypes.push(Archetype {
+            types,
+            keys: Vec::new(),
+            columns: columns.into_iter().map(|(_, column)| column).collect(),
+        });
+        self.archetypes.len() - 1
+    }
+
+    /// Move a key's row between tables, dropping the value of `dropped` if the destination lacks it
+    fn move_row(
+        &mut self,
+        key: Key,
+        from: usize,
+        row: usize,
+        to: usize,
+        dropped: Option<&TypeKey>,
+    ) {
+        {
+            let (source, destination) = (&self.archetypes[from], &self.archetypes[to]);
+            for (type_key, column) in source.types.iter().zip(source.columns.iter()) {
+                if Some(type_key) == dropped {
+                    column.remove_row(row);
+                } else {
+                    column.move_row(row, destination.column(type_key).unwrap());
+                }
+            }
+        }
+
+        self.swap_remove_key(from, row);
+        self.archetypes[to].keys.push(key);
+        let new_row = self.archetypes[to].keys.len() - 1;
+        self.locations.insert(key, (to as u32, new_row as u32));
+    }
+
+    /// Remove a row's key from a table, repointing the key swapped into its place
+    fn swap_remove_key(&mut self, archetype: usize, row: usize) {
+        let keys = &mut self.archetypes[archetype].keys;
+        keys.swap_remove(row);
+        if let Some(moved_key) = keys.get(row) {
+            self.locations
+                .insert(*moved_key, (archetype as u32, row as u32));
+        }
+    }
+}
@@ -104,6 +104,11 @@ where
         KeyBuilder::new(self)
     }
 
+    /// Start building a key freshly reserved from the store via Store::create_key
+    pub fn spawn(self, store: &Store<Key>) -> KeyBuilder<Key> {
+        self.key(store.create_key())
+    }
+
     pub fn map_key<F>(self, key: Key, f: F) -> Self
     where
         F: MapKeyBuilder<Key>,
@@ -111,6 +116,13 @@ where
         f(self.key(key)).finish()
     }
 
+    pub fn map_spawn<F>(self, store: &Store<Key>, f: F) -> Self
+    where
+        F: MapKeyBuilder<Key>,
+    {
+        f(self.spawn(store)).finish()
+    }
+
     pub fn map_current_key<F>(self, f: F) -> Self
     where
         F: MapCurrentKey<Key>,
@@ -136,6 +148,21 @@ where
         self.key(key).fields(tuple).finish()
     }
 
+    pub fn spawn_field<T>(self, store: &Store<Key>, field: T) -> Self
+    where
+        T: Debug + 'static,
+    {
+        self.spawn(store).field(field).finish()
+    }
+
+    pub fn spawn_fields<T>(self, store: &Store<Key>, tuple: T) -> Self
+    where
+        Key: Assemble<T>,
+        T: Debug + 'static,
+    {
+        self.spawn(store).fields(tuple).finish()
+    }
+
     pub fn finish(self, db: &mut Store<Key>) {
         let (_, closures): (Vec<Key>, Vec<KeyClosures<Key>>) = self.closures.into_iter().unzip();
 
@@ -0,0 +1,180 @@
+use std::{fmt::Debug, marker::PhantomData};
+
+use crate::{u32_index, StoreKey};
+
+/// Vec-backed associative map. Keys directly index into the underlying Vec, empty indices are occupied by V::default().
+///
+/// Membership is not tracked; every index within the Vec's length reads as a value.
+pub struct DefaultVecMap<K, V>
+where
+    K: StoreKey,
+    V: Default,
+{
+    values: Vec<V>,
+    _phantom_data: PhantomData<K>,
+}
+
+impl<K, V> Debug for DefaultVecMap<K, V>
+where
+    K: StoreKey,
+    V: Debug + Default,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map()
+            .entries(self.values.iter().enumerate())
+            .finish()
+    }
+}
+
+impl<K, V> Default for DefaultVecMap<K, V>
+where
+    K: StoreKey,
+    V: Default,
+{
+    fn default() -> Self {
+        DefaultVecMap::new()
+    }
+}
+
+// Public interface
+impl<K, V> DefaultVecMap<K, V>
+where
+    K: StoreKey,
+    V: Default,
+{
+    pub fn new() -> Self {
+        DefaultVecMap {
+            values: Vec::new(),
+            _phantom_data: PhantomData,
+        }
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        DefaultVecMap {
+            values: Vec::with_capacity(capacity),
+            _phantom_data: PhantomData,
+        }
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.values.get(Self::index(key))
+    }
+
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        self.values.get_mut(Self::index(key))
+    }
+
+    /// Returns the value previously held at the key's index, which is V::default() for empty indices
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let index = Self::index(&key);
+
+        if index < self.values.len() {
+            Some(std::mem::replace(&mut self.values[index], value))
+        } else {
+            self.values.resize_with(index, V::default);
+            self.values.push(value);
+            None
+        }
+    }
+
+    /// Resets the key's index to V::default(), returning the value it held
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let value = std::mem::take(self.values.get_mut(Self::index(key))?);
+        Some(value)
+    }
+
+    pub fn clear(&mut self) {
+        self.values.clear();
+    }
+
+    /// Iterate over every index within the Vec's length, including those holding V::default()
+    pub fn iter(&self) -> impl Iterator<Item = (K, &V)>
+    where
+        K: StoreKey,
+    {
+        self.values
+            .iter()
+            .enumerate()
+            .map(|(index, value)| (K::from_index(index as u64), value))
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (K, &mut V)>
+    where
+        K: StoreKey,
+    {
+        self.values
+            .iter_mut()
+            .enumerate()
+            .map(|(index, value)| (K::from_index(index as u64), value))
+    }
+}
+
+// Private interface
+impl<K, V> DefaultVecMap<K, V>
+where
+    K: StoreKey,
+    V: Default,
+{
+    fn index(key: &K) -> usize {
+        let key = u32_index(key);
+        key as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insert() {
+        println!();
+
+        let mut vec_map: DefaultVecMap<u32, i32> = DefaultVecMap::new();
+
+        assert_eq!(vec_map.insert(0, 1), None);
+        assert_eq!(vec_map.insert(2, 2), None);
+        assert_eq!(vec_map.insert(4, 3), None);
+        assert_eq!(vec_map.insert(1, 4), Some(0));
+
+        println!("{:#?}\n", vec_map);
+
+        assert_eq!(vec_map.get(&1), Some(&4));
+        assert_eq!(vec_map.get(&3), Some(&0));
+        assert_eq!(vec_map.get(&5), None);
+    }
+
+    #[test]
+    fn remove() {
+        println!();
+
+        let mut vec_map: DefaultVecMap<u32, String> = DefaultVecMap::new();
+
+        vec_map.insert(0, "Hello".into());
+        vec_map.insert(2, "World".into());
+        println!("{:#?}\n", vec_map);
+
+        assert_eq!(vec_map.remove(&2), Some("World".into()));
+        println!("{:#?}\n", vec_map);
+
+        assert_eq!(vec_map.get(&2), Some(&String::new()));
+        assert_eq!(vec_map.remove(&3), None);
+    }
+
+    #[test]
+    fn clear() {
+        println!();
+
+        let mut vec_map: DefaultVecMap<u32, i32> = DefaultVecMap::new();
+
+        vec_map.insert(0, 0);
+        vec_map.insert(3, 3);
+
+        println!("{:#?}\n", vec_map);
+
+        vec_map.clear();
+
+        println!("{:#?}\n", vec_map);
+
+        assert_eq!(vec_map.get(&0), None);
+    }
+}
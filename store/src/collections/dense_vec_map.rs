@@ -0,0 +1,267 @@
+use std::fmt::Debug;
+
+use super::PagedSparseVecMap;
+use crate::StoreKey;
+
+/// Sparse set associative map. Keys and values are packed into parallel contiguous Vecs,
+/// with a paged sparse index mapping each key to its dense position.
+pub struct DenseVecMap<K, V>
+where
+    K: StoreKey,
+{
+    keys: Vec<K>,
+    values: Vec<V>,
+    indices: PagedSparseVecMap<K, u32>,
+}
+
+impl<K, V> Debug for DenseVecMap<K, V>
+where
+    K: StoreKey,
+    V: Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map()
+            .entries(self.keys.iter().zip(self.values.iter()))
+            .finish()
+    }
+}
+
+impl<K, V> Default for DenseVecMap<K, V>
+where
+    K: StoreKey,
+{
+    fn default() -> Self {
+        DenseVecMap::new()
+    }
+}
+
+// Public interface
+impl<K, V> DenseVecMap<K, V>
+where
+    K: StoreKey,
+{
+    pub fn new() -> Self {
+        DenseVecMap {
+            keys: Vec::new(),
+            values: Vec::new(),
+            indices: PagedSparseVecMap::new(),
+        }
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        DenseVecMap {
+            keys: Vec::with_capacity(capacity),
+            values: Vec::with_capacity(capacity),
+            indices: PagedSparseVecMap::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.indices.contains_key(key)
+    }
+
+    /// Position of the key's value within the dense arrays
+    pub fn dense_index_of(&self, key: &K) -> Option<usize> {
+        self.indices.get(key).map(|index| *index as usize)
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        let index = self.dense_index_of(key)?;
+        Some(&self.values[index])
+    }
+
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        let index = self.dense_index_of(key)?;
+        Some(&mut self.values[index])
+    }
+
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        if let Some(index) = self.dense_index_of(&key) {
+            return Some(std::mem::replace(&mut self.values[index], value));
+        }
+
+        self.indices.insert(key, self.values.len() as u32);
+        self.keys.push(key);
+        self.values.push(value);
+
+        None
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let index = self.indices.remove(key)? as usize;
+
+        // Move the last entry into the vacated slot and repoint its key
+        self.keys.swap_remove(index);
+        let value = self.values.swap_remove(index);
+        if let Some(moved_key) = self.keys.get(index) {
+            self.indices.insert(*moved_key, index as u32);
+        }
+
+        Some(value)
+    }
+
+    pub fn clear(&mut self) {
+        self.keys.clear();
+        self.values.clear();
+        self.indices.clear();
+    }
+
+    pub fn keys(&self) -> &[K] {
+        &self.keys
+    }
+
+    pub fn values(&self) -> &[V] {
+        &self.values
+    }
+
+    pub fn values_mut(&mut self) -> &mut [V] {
+        &mut self.values
+    }
+
+    /// Keys and values as parallel slices
+    pub fn as_slices(&self) -> (&[K], &[V]) {
+        (&self.keys, &self.values)
+    }
+
+    pub fn as_slices_mut(&mut self) -> (&[K], &mut [V]) {
+        (&self.keys, &mut self.values)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.keys.iter().zip(self.values.iter())
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&K, &mut V)> {
+        self.keys.iter().zip(self.values.iter_mut())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insert() {
+        println!();
+
+        let mut vec_map: DenseVecMap<u32, i32> = DenseVecMap::new();
+
+        assert_eq!(vec_map.insert(0, 0), None);
+        assert_eq!(vec_map.insert(2, 1), None);
+        assert_eq!(vec_map.insert(4, 2), None);
+        assert_eq!(vec_map.insert(6, 3), None);
+        assert_eq!(vec_map.insert(8, 4), None);
+        assert_eq!(vec_map.insert(4, 5), Some(2));
+
+        println!("{:#?}\n", vec_map);
+
+        assert_eq!(vec_map.len(), 5);
+        assert_eq!(vec_map.values(), &[0, 1, 5, 3, 4]);
+    }
+
+    #[test]
+    fn remove() {
+        println!();
+
+        let mut vec_map: DenseVecMap<u32, i32> = DenseVecMap::new();
+
+        vec_map.insert(0, 0);
+        vec_map.insert(2, 1);
+        vec_map.insert(4, 2);
+        println!("{:#?}\n", vec_map);
+
+        assert_eq!(vec_map.remove(&0), Some(0));
+        println!("{:#?}\n", vec_map);
+
+        assert_eq!(vec_map.remove(&1), None);
+        println!("{:#?}\n", vec_map);
+
+        assert_eq!(vec_map.get(&2), Some(&1));
+        assert_eq!(vec_map.get(&4), Some(&2));
+        assert_eq!(vec_map.keys(), &[4, 2]);
+
+        assert_eq!(vec_map.remove(&4), Some(2));
+        assert_eq!(vec_map.remove(&2), Some(1));
+        assert!(vec_map.is_empty());
+    }
+
+    #[test]
+    fn clear() {
+        println!();
+
+        let mut vec_map: DenseVecMap<u32, i32> = DenseVecMap::new();
+
+        vec_map.insert(0, 0);
+        vec_map.insert(1, 1);
+        vec_map.insert(2, 2);
+        vec_map.insert(3, 3);
+
+        println!("{:#?}\n", vec_map);
+
+        vec_map.clear();
+
+        println!("{:#?}\n", vec_map);
+
+        assert!(vec_map.is_empty());
+        assert!(!vec_map.contains_key(&2));
+    }
+
+    #[test]
+    fn get_mut() {
+        println!();
+
+        let mut vec_map: DenseVecMap<u32, i32> = DenseVecMap::new();
+
+        vec_map.insert(0, 0);
+        vec_map.insert(3, 3);
+
+        *vec_map.get_mut(&3).unwrap() += 1;
+
+        assert_eq!(vec_map.get(&3), Some(&4));
+        assert_eq!(vec_map.get_mut(&1), None);
+        assert_eq!(vec_map.get_mut(&100), None);
+    }
+
+    #[test]
+    fn sparse_keys() {
+        let mut vec_map: DenseVecMap<u32, i32> = DenseVecMap::new();
+
+        assert_eq!(vec_map.insert(8, 0), None);
+        assert_eq!(vec_map.insert(10_000_000, 1), None);
+        assert_eq!(vec_map.insert(2, 2), None);
+        println!("{:#?}\n", vec_map);
+
+        assert_eq!(vec_map.keys(), &[8, 10_000_000, 2]);
+        assert_eq!(vec_map.get(&10_000_000), Some(&1));
+        assert_eq!(vec_map.dense_index_of(&2), Some(2));
+        assert_eq!(vec_map.get(&5), None);
+
+        assert_eq!(vec_map.remove(&8), Some(0));
+        assert_eq!(vec_map.dense_index_of(&2), Some(0));
+        assert_eq!(vec_map.keys(), &[2, 10_000_000]);
+    }
+
+    #[test]
+    fn iter_mut() {
+        let mut vec_map: DenseVecMap<u32, i32> = DenseVecMap::new();
+
+        vec_map.insert(3, 1);
+        vec_map.insert(1, 2);
+
+        for (key, value) in vec_map.iter_mut() {
+            *value += *key as i32;
+        }
+        vec_map.values_mut()[0] *= 10;
+
+        assert_eq!(vec_map.get(&3), Some(&40));
+        assert_eq!(vec_map.get(&1), Some(&3));
+    }
+}
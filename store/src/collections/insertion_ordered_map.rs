@@ -0,0 +1,196 @@
+use std::fmt::Debug;
+
+use super::PagedSparseVecMap;
+use crate::StoreKey;
+
+/// Vec-backed associative map that keeps entries in the order their keys were first inserted.
+///
+/// Keys and values are packed into parallel Vecs like DenseVecMap, but removal shifts later entries down
+/// to preserve order, making it O(n) in the number of entries after the removed one.
+pub struct InsertionOrderedMap<K, V>
+where
+    K: StoreKey,
+{
+    keys: Vec<K>,
+    values: Vec<V>,
+    indices: PagedSparseVecMap<K, u32>,
+}
+
+impl<K, V> Debug for InsertionOrderedMap<K, V>
+where
+    K: StoreKey,
+    V: Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map()
+            .entries(self.keys.iter().zip(self.values.iter()))
+            .finish()
+    }
+}
+
+impl<K, V> Default for InsertionOrderedMap<K, V>
+where
+    K: StoreKey,
+{
+    fn default() -> Self {
+        InsertionOrderedMap::new()
+    }
+}
+
+// Public interface
+impl<K, V> InsertionOrderedMap<K, V>
+where
+    K: StoreKey,
+{
+    pub fn new() -> Self {
+        InsertionOrderedMap {
+            keys: Vec::new(),
+            values: Vec::new(),
+            indices: PagedSparseVecMap::new(),
+        }
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        InsertionOrderedMap {
+            keys: Vec::with_capacity(capacity),
+            values: Vec::with_capacity(capacity),
+            indices: PagedSparseVecMap::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.indices.contains_key(key)
+    }
+
+    /// Position of the key's entry in insertion order
+    pub fn index_of(&self, key: &K) -> Option<usize> {
+        self.indices.get(key).map(|index| *index as usize)
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        let index = self.index_of(key)?;
+        Some(&self.values[index])
+    }
+
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        let index = self.index_of(key)?;
+        Some(&mut self.values[index])
+    }
+
+    /// Replacing the value of an existing key keeps its original position
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        if let Some(index) = self.index_of(&key) {
+            return Some(std::mem::replace(&mut self.values[index], value));
+        }
+
+        self.indices.insert(key, self.values.len() as u32);
+        self.keys.push(key);
+        self.values.push(value);
+
+        None
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let index = self.indices.remove(key)? as usize;
+
+        // Shift later entries down to keep the remaining order intact
+        self.keys.remove(index);
+        let value = self.values.remove(index);
+        for (index, key) in self.keys.iter().enumerate().skip(index) {
+            self.indices.insert(*key, index as u32);
+        }
+
+        Some(value)
+    }
+
+    pub fn clear(&mut self) {
+        self.keys.clear();
+        self.values.clear();
+        self.indices.clear();
+    }
+
+    pub fn keys(&self) -> &[K] {
+        &self.keys
+    }
+
+    pub fn values(&self) -> &[V] {
+        &self.values
+    }
+
+    pub fn values_mut(&mut self) -> &mut [V] {
+        &mut self.values
+    }
+
+    /// Keys and values as parallel slices
+    pub fn as_slices(&self) -> (&[K], &[V]) {
+        (&self.keys, &self.values)
+    }
+
+    pub fn as_slices_mut(&mut self) -> (&[K], &mut [V]) {
+        (&self.keys, &mut self.values)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.keys.iter().zip(self.values.iter())
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&K, &mut V)> {
+        self.keys.iter().zip(self.values.iter_mut())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insert() {
+        println!();
+
+        let mut ordered_map: InsertionOrderedMap<u32, &str> = InsertionOrderedMap::new();
+
+        assert_eq!(ordered_map.insert(9, "first"), None);
+        assert_eq!(ordered_map.insert(2, "second"), None);
+        assert_eq!(ordered_map.insert(5, "third"), None);
+        assert_eq!(ordered_map.insert(2, "replaced"), Some("second"));
+
+        println!("{:#?}\n", ordered_map);
+
+        assert_eq!(ordered_map.keys(), &[9, 2, 5]);
+        assert_eq!(ordered_map.values(), &["first", "replaced", "third"]);
+    }
+
+    #[test]
+    fn remove() {
+        println!();
+
+        let mut ordered_map: InsertionOrderedMap<u32, i32> = InsertionOrderedMap::new();
+
+        for key in &[4, 0, 3, 1, 2] {
+            ordered_map.insert(*key, *key as i32 * 10);
+        }
+
+        assert_eq!(ordered_map.remove(&0), Some(0));
+        assert_eq!(ordered_map.remove(&0), None);
+        println!("{:#?}\n", ordered_map);
+
+        assert_eq!(ordered_map.keys(), &[4, 3, 1, 2]);
+        assert_eq!(ordered_map.index_of(&2), Some(3));
+        assert_eq!(ordered_map.get(&1), Some(&10));
+
+        ordered_map.insert(0, 0);
+        assert_eq!(ordered_map.keys(), &[4, 3, 1, 2, 0]);
+
+        ordered_map.clear();
+        assert!(ordered_map.is_empty());
+        assert_eq!(ordered_map.get(&4), None);
+    }
+}
@@ -1,9 +1,19 @@
 mod btree_map;
+mod default_vec_map;
+mod dense_vec_map;
 mod hash_map;
 mod hash_set;
+mod insertion_ordered_map;
+mod paged_sparse_vec_map;
+mod roaring_set;
 mod sparse_vec_map;
 
 pub use btree_map::*;
+pub use default_vec_map::*;
+pub use dense_vec_map::*;
 pub use hash_map::*;
 pub use hash_set::*;
-pub use sparse_vec_map::*;
\ No newline at end of file
+pub use insertion_ordered_map::*;
+pub use paged_sparse_vec_map::*;
+pub use roaring_set::*;
+pub use sparse_vec_map::*;
@@ -0,0 +1,345 @@
+use std::{fmt::Debug, marker::PhantomData, mem::MaybeUninit};
+
+use crate::{u32_index, StoreKey};
+
+/// Number of slots allocated together in a single page
+pub const PAGE_SIZE: usize = 1024;
+
+const PAGE_WORDS: usize = PAGE_SIZE / 64;
+
+/// Fixed-size block of slots with its own occupancy mask
+struct Page<V> {
+    occupied: [u64; PAGE_WORDS],
+    len: usize,
+    values: Box<[MaybeUninit<V>]>,
+}
+
+impl<V> Page<V> {
+    fn new() -> Self {
+        Page {
+            occupied: [0; PAGE_WORDS],
+            len: 0,
+            values: (0..PAGE_SIZE).map(|_| MaybeUninit::uninit()).collect(),
+        }
+    }
+
+    fn contains(&self, slot: usize) -> bool {
+        self.occupied[slot / 64] & (1 << (slot % 64)) != 0
+    }
+
+    fn occupy(&mut self, slot: usize) {
+        self.occupied[slot / 64] |= 1 << (slot % 64);
+        self.len += 1;
+    }
+
+    fn vacate(&mut self, slot: usize) {
+        self.occupied[slot / 64] &= !(1 << (slot % 64));
+        self.len -= 1;
+    }
+}
+
+impl<V> Drop for Page<V> {
+    fn drop(&mut self) {
+        for slot in 0..PAGE_SIZE {
+            if self.contains(slot) {
+                unsafe { self.values[slot].assume_init_drop() };
+            }
+        }
+    }
+}
+
+/// Vec-backed associative map that allocates slots in pages of PAGE_SIZE on demand.
+///
+/// Like SparseVecMap, keys index directly into storage, but only pages holding at least one value are allocated,
+/// and pages are freed once emptied.
+pub struct PagedSparseVecMap<K, V>
+where
+    K: StoreKey,
+{
+    pages: Vec<Option<Box<Page<V>>>>,
+    len: usize,
+    _phantom_data: PhantomData<K>,
+}
+
+impl<K, V> Debug for PagedSparseVecMap<K, V>
+where
+    K: StoreKey,
+    V: Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map().entries(self.iter_indices()).finish()
+    }
+}
+
+impl<K, V> Default for PagedSparseVecMap<K, V>
+where
+    K: StoreKey,
+{
+    fn default() -> Self {
+        PagedSparseVecMap::new()
+    }
+}
+
+// Public interface
+impl<K, V> PagedSparseVecMap<K, V>
+where
+    K: StoreKey,
+{
+    pub fn new() -> Self {
+        PagedSparseVecMap {
+            pages: Vec::new(),
+            len: 0,
+            _phantom_data: PhantomData,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Number of pages currently allocated
+    pub fn page_count(&self) -> usize {
+        self.pages.iter().filter(|page| page.is_some()).count()
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        let (page, slot) = Self::locate(key);
+        match self.pages.get(page) {
+            Some(Some(page)) => page.contains(slot),
+            _ => false,
+        }
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        let (page, slot) = Self::locate(key);
+        let page = self.pages.get(page)?.as_ref()?;
+        if page.contains(slot) {
+            Some(unsafe { page.values[slot].assume_init_ref() })
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        let (page, slot) = Self::locate(key);
+        let page = self.pages.get_mut(page)?.as_mut()?;
+        if page.contains(slot) {
+            Some(unsafe { page.values[slot].assume_init_mut() })
+        } else {
+            None
+        }
+    }
+
+    /// Returns the value previously stored under the key, if any
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let (page, slot) = Self::locate(&key);
+
+        if page >= self.pages.len() {
+            self.pages.resize_with(page + 1, || None);
+        }
+
+        let page = self.pages[page].get_or_insert_with(|| Box::new(Page::new()));
+        if page.contains(slot) {
+            let existing = unsafe { page.values[slot].assume_init_mut() };
+            Some(std::mem::replace(existing, value))
+        } else {
+            page.values[slot] = MaybeUninit::new(value);
+            page.occupy(slot);
+            self.len += 1;
+            None
+        }
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let (page_index, slot) = Self::locate(key);
+        let page = self.pages.get_mut(page_index)?.as_mut()?;
+        if !page.contains(slot) {
+            return None;
+        }
+
+        page.vacate(slot);
+        let value = unsafe { page.values[slot].assume_init_read() };
+        self.len -= 1;
+
+        // Free emptied pages, and trim trailing unallocated ones from the page table
+        if page.len == 0 {
+            self.pages[page_index] = None;
+            while let Some(None) = self.pages.last() {
+                self.pages.pop();
+            }
+        }
+
+        Some(value)
+    }
+
+    pub fn clear(&mut self) {
+        self.pages.clear();
+        self.len = 0;
+    }
+}
+
+// Iteration interface
+impl<K, V> PagedSparseVecMap<K, V>
+where
+    K: StoreKey,
+{
+    pub fn iter(&self) -> impl Iterator<Item = (K, &V)> {
+        self.iter_indices()
+            .map(|(index, value)| (K::from_index(u64::from(index)), value))
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (K, &mut V)> {
+        self.pages
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(page_index, page)| Some((page_index, page.as_mut()?)))
+            .flat_map(|(page_index, page)| {
+                let occupied = page.occupied;
+                page.values
+                    .iter_mut()
+                    .enumerate()
+                    .filter(move |(slot, _)| occupied[slot / 64] & (1 << (slot % 64)) != 0)
+                    .map(move |(slot, value)| {
+                        (
+                            K::from_index((page_index * PAGE_SIZE + slot) as u64),
+                            unsafe { value.assume_init_mut() },
+                        )
+                    })
+            })
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = K> + '_ {
+        self.iter().map(|(key, _)| key)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.iter_indices().map(|(_, value)| value)
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut V> {
+        self.iter_mut().map(|(_, value)| value)
+    }
+}
+
+// Private interface
+impl<K, V> PagedSparseVecMap<K, V>
+where
+    K: StoreKey,
+{
+    fn locate(key: &K) -> (usize, usize)
+    where
+        K: Copy,
+    {
+        let key = u32_index(key);
+        let key = key as usize;
+        (key / PAGE_SIZE, key % PAGE_SIZE)
+    }
+
+    fn iter_indices(&self) -> impl Iterator<Item = (u32, &V)> {
+        self.pages
+            .iter()
+            .enumerate()
+            .filter_map(|(page_index, page)| Some((page_index, page.as_ref()?)))
+            .flat_map(|(page_index, page)| {
+                (0..PAGE_SIZE)
+                    .filter(move |slot| page.contains(*slot))
+                    .map(move |slot| {
+                        ((page_index * PAGE_SIZE + slot) as u32, unsafe {
+                            page.values[slot].assume_init_ref()
+                        })
+                    })
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+
+    #[test]
+    fn insert() {
+        println!();
+
+        let mut vec_map: PagedSparseVecMap<u32, i32> = PagedSparseVecMap::new();
+
+        assert_eq!(vec_map.insert(0, 0), None);
+        assert_eq!(vec_map.insert(2, 1), None);
+        assert_eq!(vec_map.insert(10_000_000, 2), None);
+        assert_eq!(vec_map.insert(2, 3), Some(1));
+
+        println!("{:#?}\n", vec_map);
+
+        assert_eq!(vec_map.len(), 3);
+        assert_eq!(vec_map.page_count(), 2);
+        assert_eq!(vec_map.get(&2), Some(&3));
+        assert_eq!(vec_map.get(&10_000_000), Some(&2));
+        assert_eq!(vec_map.get(&9_999_999), None);
+        assert_eq!(vec_map.get(&20_000_000), None);
+    }
+
+    #[test]
+    fn remove() {
+        println!();
+
+        let mut vec_map: PagedSparseVecMap<u32, i32> = PagedSparseVecMap::new();
+
+        vec_map.insert(1, 1);
+        vec_map.insert(PAGE_SIZE as u32, 2);
+        vec_map.insert(PAGE_SIZE as u32 * 4, 3);
+        assert_eq!(vec_map.page_count(), 3);
+
+        assert_eq!(vec_map.remove(&(PAGE_SIZE as u32)), Some(2));
+        assert_eq!(vec_map.remove(&(PAGE_SIZE as u32)), None);
+        assert_eq!(vec_map.page_count(), 2);
+
+        assert_eq!(vec_map.remove(&(PAGE_SIZE as u32 * 4)), Some(3));
+        assert_eq!(vec_map.pages.len(), 1);
+
+        println!("{:#?}\n", vec_map);
+    }
+
+    #[test]
+    fn iter() {
+        let mut vec_map: PagedSparseVecMap<u32, i32> = PagedSparseVecMap::new();
+
+        vec_map.insert(5000, 3);
+        vec_map.insert(3, 1);
+        vec_map.insert(1030, 2);
+
+        for value in vec_map.values_mut() {
+            *value *= 10;
+        }
+
+        assert_eq!(
+            vec_map.iter().collect::<Vec<_>>(),
+            vec![(3, &10), (1030, &20), (5000, &30)]
+        );
+        assert_eq!(vec_map.keys().collect::<Vec<_>>(), vec![3, 1030, 5000]);
+    }
+
+    #[test]
+    fn drop_values() {
+        let value = Rc::new(());
+
+        let mut vec_map: PagedSparseVecMap<u32, Rc<()>> = PagedSparseVecMap::new();
+        vec_map.insert(0, value.clone());
+        vec_map.insert(3000, value.clone());
+        vec_map.insert(3000, value.clone());
+        assert_eq!(Rc::strong_count(&value), 3);
+
+        vec_map.remove(&0);
+        assert_eq!(Rc::strong_count(&value), 2);
+
+        vec_map.clear();
+        assert_eq!(Rc::strong_count(&value), 1);
+
+        vec_map.insert(7, value.clone());
+        drop(vec_map);
+        assert_eq!(Rc::strong_count(&value), 1);
+    }
+}
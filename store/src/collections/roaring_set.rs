@@ -0,0 +1,519 @@
+use std::{
+    collections::{btree_map, BTreeMap},
+    fmt::Debug,
+    ops::{Bound, RangeBounds},
+};
+
+const BITMAP_WORDS: usize = 1024;
+
+/// Containers holding at most this many values are kept as sorted arrays, and larger ones as bitmaps
+const ARRAY_LIMIT: usize = 4096;
+
+/// Compressed set of u64 indices, roaring-style.
+///
+/// Indices are grouped by their high 48 bits, and each group's low 16 bits are held in a container
+/// that is a sorted array while sparse and a 65536-bit bitmap once dense.
+/// Memory is proportional to the number of indices rather than to the largest one.
+#[derive(Default, Clone, Eq, PartialEq)]
+pub struct RoaringSet {
+    containers: BTreeMap<u64, Container>,
+    len: usize,
+}
+
+#[derive(Clone, Eq, PartialEq)]
+enum Container {
+    Array(Vec<u16>),
+    Bitmap(Box<[u64; BITMAP_WORDS]>, usize),
+}
+
+impl Debug for RoaringSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+// Public interface
+impl RoaringSet {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn contains(&self, index: u64) -> bool {
+        let (high, low) = split(index);
+        self.containers
+            .get(&high)
+            .is_some_and(|container| container.contains(low))
+    }
+
+    /// Returns true if the index was not already present
+    pub fn insert(&mut self, index: u64) -> bool {
+        let (high, low) = split(index);
+        let inserted = self
+            .containers
+            .entry(high)
+            .or_insert_with(|| Container::Array(Vec::new()))
+            .insert(low);
+        if inserted {
+            self.len += 1;
+        }
+        inserted
+    }
+
+    /// Returns true if the index was present
+    pub fn remove(&mut self, index: u64) -> bool {
+        let (high, low) = split(index);
+        let container = match self.containers.get_mut(&high) {
+            Some(container) => container,
+            None => return false,
+        };
+
+        if !container.remove(low) {
+            return false;
+        }
+
+        if container.len() == 0 {
+            self.containers.remove(&high);
+        }
+        self.len -= 1;
+        true
+    }
+
+    pub fn clear(&mut self) {
+        self.containers.clear();
+        self.len = 0;
+    }
+
+    /// Indices in ascending order
+    pub fn iter(&self) -> RoaringIter<'_> {
+        RoaringIter {
+            containers: self.containers.iter(),
+            current: None,
+        }
+    }
+
+    /// Indices within a range in ascending order, visiting only the containers the range overlaps
+    pub fn range<R>(&self, range: R) -> impl Iterator<Item = u64> + '_
+    where
+        R: RangeBounds<u64>,
+    {
+        let start = match range.start_bound() {
+            Bound::Included(start) => Some(*start),
+            Bound::Excluded(start) => start.checked_add(1),
+            Bound::Unbounded => Some(0),
+        };
+        let end = match range.end_bound() {
+            Bound::Included(end) => Some(*end),
+            Bound::Excluded(end) => end.checked_sub(1),
+            Bound::Unbounded => Some(u64::MAX),
+        };
+
+        let bounds = match (start, end) {
+            (Some(start), Some(end)) if start <= end => Some((start, end)),
+            _ => None,
+        };
+
+        bounds.into_iter().flat_map(move |(start, end)| {
+            self.containers
+                .range(split(start).0..=split(end).0)
+                .flat_map(|(high, container)| container.iter().map(move |low| join(*high, low)))
+                .filter(move |index| (start..=end).contains(index))
+        })
+    }
+
+    pub fn union_with(&mut self, other: &RoaringSet) {
+        for (high, other_container) in &other.containers {
+            match self.containers.get_mut(high) {
+                Some(container) => *container = container.union(other_container),
+                None => {
+                    self.containers.insert(*high, other_container.clone());
+                }
+            }
+        }
+        self.recount();
+    }
+
+    pub fn intersect_with(&mut self, other: &RoaringSet) {
+        self.containers = std::mem::take(&mut self.containers)
+            .into_iter()
+            .filter_map(|(high, container)| {
+                let container = container.intersection(other.containers.get(&high)?);
+                Some((high, container))
+            })
+            .filter(|(_, container)| container.len() != 0)
+            .collect();
+        self.recount();
+    }
+
+    pub fn difference_with(&mut self, other: &RoaringSet) {
+        self.containers = std::mem::take(&mut self.containers)
+            .into_iter()
+            .map(|(high, container)| match other.containers.get(&high) {
+                Some(other_container) => (high, container.difference(other_container)),
+                None => (high, container),
+            })
+            .filter(|(_, container)| container.len() != 0)
+            .collect();
+        self.recount();
+    }
+}
+
+// Private interface
+impl RoaringSet {
+    fn recount(&mut self) {
+        self.len = self.containers.values().map(Container::len).sum();
+    }
+}
+
+fn split(index: u64) -> (u64, u16) {
+    (index >> 16, index as u16)
+}
+
+fn join(high: u64, low: u16) -> u64 {
+    (high << 16) | u64::from(low)
+}
+
+impl Container {
+    fn len(&self) -> usize {
+        match self {
+            Container::Array(values) => values.len(),
+            Container::Bitmap(_, len) => *len,
+        }
+    }
+
+    fn contains(&self, low: u16) -> bool {
+        match self {
+            Container::Array(values) => values.binary_search(&low).is_ok(),
+            Container::Bitmap(words, _) => words[low as usize / 64] & (1 << (low % 64)) != 0,
+        }
+    }
+
+    fn insert(&mut self, low: u16) -> bool {
+        match self {
+            Container::Array(values) => {
+                let index = match values.binary_search(&low) {
+                    Ok(_) => return false,
+                    Err(index) => index,
+                };
+                values.insert(index, low);
+                if values.len() > ARRAY_LIMIT {
+                    *self = Container::bitmap(self.to_words());
+                }
+                true
+            }
+            Container::Bitmap(words, len) => {
+                let (word, bit) = (low as usize / 64, 1 << (low % 64));
+                if words[word] & bit != 0 {
+                    return false;
+                }
+                words[word] |= bit;
+                *len += 1;
+                true
+            }
+        }
+    }
+
+    fn remove(&mut self, low: u16) -> bool {
+        match self {
+            Container::Array(values) => match values.binary_search(&low) {
+                Ok(index) => {
+                    values.remove(index);
+                    true
+                }
+                Err(_) => false,
+            },
+            Container::Bitmap(words, len) => {
+                let (word, bit) = (low as usize / 64, 1 << (low % 64));
+                if words[word] & bit == 0 {
+                    return false;
+                }
+                words[word] &= !bit;
+                *len -= 1;
+                if *len <= ARRAY_LIMIT {
+                    *self = Container::Array(self.iter().collect());
+                }
+                true
+            }
+        }
+    }
+
+    fn iter(&self) -> ContainerIter<'_> {
+        match self {
+            Container::Array(values) => ContainerIter::Array(values.iter()),
+            Container::Bitmap(words, _) => ContainerIter::Bitmap {
+                words: &words[..],
+                word: 0,
+                bits: words[0],
+            },
+        }
+    }
+
+    fn to_words(&self) -> Box<[u64; BITMAP_WORDS]> {
+        match self {
+            Container::Array(values) => {
+                let mut words = Box::new([0; BITMAP_WORDS]);
+                for low in values {
+                    words[*low as usize / 64] |= 1 << (low % 64);
+                }
+                words
+            }
+            Container::Bitmap(words, _) => words.clone(),
+        }
+    }
+
+    /// Build a container from bitmap words, as an array if sparse enough
+    fn bitmap(words: Box<[u64; BITMAP_WORDS]>) -> Container {
+        let len = words.iter().map(|word| word.count_ones() as usize).sum();
+        let container = Container::Bitmap(words, len);
+        if len <= ARRAY_LIMIT {
+            Container::Array(container.iter().collect())
+        } else {
+            container
+        }
+    }
+
+    fn combine<F>(&self, other: &Container, f: F) -> Container
+    where
+        F: Fn(u64, u64) -> u64,
+    {
+        let mut words = self.to_words();
+        for (word, other_word) in words.iter_mut().zip(other.to_words().iter()) {
+            *word = f(*word, *other_word);
+        }
+        Container::bitmap(words)
+    }
+
+    fn union(&self, other: &Container) -> Container {
+        match (self, other) {
+            (Container::Array(values), Container::Array(other_values))
+                if values.len() + other_values.len() <= ARRAY_LIMIT =>
+            {
+                let mut values: Vec<u16> = values.iter().chain(other_values).copied().collect();
+                values.sort_unstable();
+                values.dedup();
+                Container::Array(values)
+            }
+            _ => self.combine(other, |word, other_word| word | other_word),
+        }
+    }
+
+    fn intersection(&self, other: &Container) -> Container {
+        match self {
+            Container::Array(values) => Container::Array(
+                values
+                    .iter()
+                    .copied()
+                    .filter(|low| other.contains(*low))
+                    .collect(),
+            ),
+            Container::Bitmap(..) => self.combine(other, |word, other_word| word & other_word),
+        }
+    }
+
+    fn difference(&self, other: &Container) -> Container {
+        match self {
+            Container::Array(values) => Container::Array(
+                values
+                    .iter()
+                    .copied()
+                    .filter(|low| !other.contains(*low))
+                    .collect(),
+            ),
+            Container::Bitmap(..) => self.combine(other, |word, other_word| word & !other_word),
+        }
+    }
+}
+
+/// Low bits held by a container in ascending order, walking a bitmap's set bits word by word
+enum ContainerIter<'a> {
+    Array(std::slice::Iter<'a, u16>),
+    Bitmap {
+        words: &'a [u64],
+        word: usize,
+        bits: u64,
+    },
+}
+
+impl<'a> Iterator for ContainerIter<'a> {
+    type Item = u16;
+
+    fn next(&mut self) -> Option<u16> {
+        match self {
+            ContainerIter::Array(values) => values.next().copied(),
+            ContainerIter::Bitmap { words, word, bits } => {
+                while *bits == 0 {
+                    *word += 1;
+                    *bits = *words.get(*word)?;
+                }
+                let low = *word * 64 + bits.trailing_zeros() as usize;
+                *bits &= *bits - 1;
+                Some(low as u16)
+            }
+        }
+    }
+}
+
+/// Borrowing iterator over a RoaringSet's indices in ascending order
+pub struct RoaringIter<'a> {
+    containers: btree_map::Iter<'a, u64, Container>,
+    current: Option<(u64, ContainerIter<'a>)>,
+}
+
+impl<'a> Iterator for RoaringIter<'a> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        loop {
+            if let Some((high, values)) = &mut self.current {
+                if let Some(low) = values.next() {
+                    return Some(join(*high, low));
+                }
+            }
+
+            let (high, container) = self.containers.next()?;
+            self.current = Some((*high, container.iter()));
+        }
+    }
+}
+
+/// Owning iterator over a RoaringSet's indices in ascending order
+pub struct RoaringIntoIter {
+    containers: btree_map::IntoIter<u64, Container>,
+    current: Option<(u64, std::vec::IntoIter<u16>)>,
+}
+
+impl Iterator for RoaringIntoIter {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        loop {
+            if let Some((high, values)) = &mut self.current {
+                if let Some(low) = values.next() {
+                    return Some(join(*high, low));
+                }
+            }
+
+            let (high, container) = self.containers.next()?;
+            let values = match container {
+                Container::Array(values) => values,
+                container => container.iter().collect(),
+            };
+            self.current = Some((high, values.into_iter()));
+        }
+    }
+}
+
+impl IntoIterator for RoaringSet {
+    type Item = u64;
+    type IntoIter = RoaringIntoIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        RoaringIntoIter {
+            containers: self.containers.into_iter(),
+            current: None,
+        }
+    }
+}
+
+impl std::iter::FromIterator<u64> for RoaringSet {
+    fn from_iter<T: IntoIterator<Item = u64>>(iter: T) -> Self {
+        let mut set = RoaringSet::new();
+        for index in iter {
+            set.insert(index);
+        }
+        set
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roaring_set() {
+        let mut set = RoaringSet::new();
+        assert!(set.insert(3));
+        assert!(!set.insert(3));
+        assert!(set.insert(1 << 40));
+        assert!(set.insert(u64::MAX));
+        assert!(set.insert(70_000));
+        println!("{:?}", set);
+
+        assert_eq!(set.len(), 4);
+        assert!(set.contains(1 << 40));
+        assert!(!set.contains(4));
+        assert_eq!(
+            set.iter().collect::<Vec<_>>(),
+            vec![3, 70_000, 1 << 40, u64::MAX]
+        );
+        assert_eq!(
+            set.range(4..=(1 << 40)).collect::<Vec<_>>(),
+            vec![70_000, 1 << 40]
+        );
+        assert_eq!(set.range(5..5).count(), 0);
+
+        assert!(set.remove(1 << 40));
+        assert!(!set.remove(1 << 40));
+        assert_eq!(set.len(), 3);
+        assert_eq!(
+            set.clone().into_iter().collect::<Vec<_>>(),
+            vec![3, 70_000, u64::MAX]
+        );
+    }
+
+    #[test]
+    fn containers() {
+        // Fill one container past the array limit so it becomes a bitmap, then drain it back
+        let mut set: RoaringSet = (0..ARRAY_LIMIT as u64 + 10)
+            .map(|index| index * 2)
+            .collect();
+        assert!(matches!(set.containers[&0], Container::Bitmap(..)));
+        assert_eq!(set.len(), ARRAY_LIMIT + 10);
+        assert!(set
+            .iter()
+            .eq((0..ARRAY_LIMIT as u64 + 10).map(|index| index * 2)));
+
+        for index in 0..20 {
+            set.remove(index * 2);
+        }
+        assert!(matches!(set.containers[&0], Container::Array(_)));
+        assert_eq!(set.iter().next(), Some(40));
+        assert_eq!(set.len(), ARRAY_LIMIT - 10);
+    }
+
+    #[test]
+    fn set_operations() {
+        let evens: RoaringSet = (0..10_000).map(|index| index * 2).collect();
+        let low: RoaringSet = (0..100).chain(1 << 50..(1 << 50) + 3).collect();
+
+        let mut union = low.clone();
+        union.union_with(&evens);
+        assert_eq!(union.len(), 10_000 + 50 + 3);
+
+        let mut intersection = low.clone();
+        intersection.intersect_with(&evens);
+        assert_eq!(intersection.len(), 50);
+        assert!(intersection
+            .iter()
+            .all(|index| index % 2 == 0 && index < 100));
+
+        let mut difference = evens.clone();
+        difference.difference_with(&low);
+        assert_eq!(difference.len(), 10_000 - 50);
+        assert_eq!(difference.iter().next(), Some(100));
+
+        let mut difference = low;
+        difference.difference_with(&evens);
+        assert_eq!(
+            difference.iter().skip(50).collect::<Vec<_>>(),
+            vec![1 << 50, (1 << 50) + 1, (1 << 50) + 2]
+        );
+    }
+}
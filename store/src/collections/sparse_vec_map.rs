@@ -1,121 +1,589 @@
-use std::{fmt::Debug, marker::PhantomData, mem::MaybeUninit};
+use hibitset::{BitIter, BitSet, BitSetLike};
+use std::{
+    fmt::Debug,
+    iter::FromIterator,
+    marker::PhantomData,
+    mem::MaybeUninit,
+    ops::{Index, IndexMut},
+};
+
+use crate::{u32_index, StoreKey};
 
 /// Vec-backed associative map. Keys directly index into the underlying Vec, empty indices are uninitialized memory.
+///
+/// Occupied indices are tracked by an internal BitSet, so values are dropped correctly on replace, remove, clear and drop.
+/// Keys are not stored, so iteration yields them by value in ascending order.
 pub struct SparseVecMap<K, V>
 where
-    K: Into<u32>,
+    K: StoreKey,
 {
+    keys: BitSet,
     values: Vec<MaybeUninit<V>>,
+    len: usize,
     _phantom_data: PhantomData<K>,
 }
 
 impl<K, V> Debug for SparseVecMap<K, V>
 where
-    K: Debug + Into<u32>,
+    K: StoreKey,
     V: Debug,
 {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_map()
-            .entries(self.values.iter().enumerate())
+            .entries((&self.keys).iter().map(|index| {
+                (index, unsafe {
+                    self.values[index as usize].assume_init_ref()
+                })
+            }))
             .finish()
     }
 }
 
 impl<K, V> Default for SparseVecMap<K, V>
 where
-    K: Copy + Into<u32>,
+    K: StoreKey,
 {
     fn default() -> Self {
         SparseVecMap::new()
     }
 }
 
+impl<K, V> Drop for SparseVecMap<K, V>
+where
+    K: StoreKey,
+{
+    fn drop(&mut self) {
+        self.drop_values();
+    }
+}
+
 // Public interface
 impl<K, V> SparseVecMap<K, V>
 where
-    K: Copy + Into<u32>,
+    K: StoreKey,
 {
     pub fn new() -> Self {
         SparseVecMap {
+            keys: BitSet::new(),
             values: Vec::new(),
+            len: 0,
             _phantom_data: PhantomData,
         }
     }
 
     pub fn with_capacity(capacity: usize) -> Self {
         SparseVecMap {
+            keys: BitSet::with_capacity(capacity as u32),
             values: Vec::with_capacity(capacity),
+            len: 0,
             _phantom_data: PhantomData,
         }
     }
 
-    pub fn get(&self, key: &K) -> &V {
-        let key: u32 = (*key).into();
-        let value = &self.values[key as usize];
-        let out: &V;
-        unsafe {
-            let value: *const V = value.as_ptr();
-            out = &*value;
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.keys.contains(u32_index(key))
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        if self.contains_key(key) {
+            Some(unsafe { self.values[Self::slot(key)].assume_init_ref() })
+        } else {
+            None
         }
-        out
     }
 
-    pub fn get_mut(&mut self, key: &K) -> &mut V {
-        let key: u32 = (*key).into();
-        let value = &mut self.values[key as usize];
-        let out: &mut V;
-        unsafe {
-            let value: *mut V = value.as_mut_ptr();
-            out = &mut *value;
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        if self.contains_key(key) {
+            Some(unsafe { self.values[Self::slot(key)].assume_init_mut() })
+        } else {
+            None
         }
-        out
     }
 
-    pub fn insert(&mut self, key: K, value: V, existing: bool) {
-        let key: u32 = key.into();
-        let key: usize = key as usize;
+    /// Returns the value previously stored under the key, if any
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let slot = Self::slot(&key);
 
-        // If the new key is outside the array's length, resize to the correct length - 1
-        if key > self.values.len() {
-            self.values
-                .resize_with(key, || unsafe { MaybeUninit::uninit().assume_init() });
+        // Grow to cover the new index, leaving any gap uninitialized
+        if slot >= self.values.len() {
+            self.values.resize_with(slot + 1, MaybeUninit::uninit);
         }
 
-        // Push the new value onto the end of the array
-        self.values.push(MaybeUninit::new(value));
-
-        if key < self.values.len() - 1 {
-            let value = self.values.swap_remove(key);
-            if existing {
-                unsafe {
-                    value.assume_init();
-                }
-            }
+        if self.keys.add(u32_index(&key)) {
+            let existing = unsafe { self.values[slot].assume_init_mut() };
+            Some(std::mem::replace(existing, value))
+        } else {
+            self.values[slot] = MaybeUninit::new(value);
+            self.len += 1;
+            None
         }
     }
 
-    pub fn remove(&mut self, key: &K) {
-        self.values
-            .push(unsafe { MaybeUninit::uninit().assume_init() });
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        if self.keys.remove(u32_index(key)) {
+            self.len -= 1;
+            Some(unsafe { self.values[Self::slot(key)].assume_init_read() })
+        } else {
+            None
+        }
+    }
 
-        let key: u32 = (*key).into();
-        unsafe {
-            self.values.swap_remove(key as usize).assume_init();
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V> {
+        if self.contains_key(&key) {
+            Entry::Occupied(OccupiedEntry { map: self, key })
+        } else {
+            Entry::Vacant(VacantEntry { map: self, key })
         }
     }
 
     pub fn clear(&mut self) {
-        for value in self.values.drain(..) {
-            unsafe {
-                value.assume_init();
+        self.drop_values();
+    }
+
+    /// Release memory past the highest occupied key
+    pub fn shrink_to_fit(&mut self) {
+        let len = (&self.keys)
+            .iter()
+            .last()
+            .map_or(0, |index| index as usize + 1);
+        self.values.truncate(len);
+        self.values.shrink_to_fit();
+    }
+}
+
+// Iteration interface
+impl<K, V> SparseVecMap<K, V>
+where
+    K: StoreKey,
+{
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            keys: (&self.keys).iter(),
+            values: &self.values,
+            len: self.len,
+            _phantom_data: PhantomData,
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        IterMut {
+            keys: (&self.keys).iter(),
+            values: self.values.as_mut_ptr(),
+            len: self.len,
+            _phantom_data: PhantomData,
+        }
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = K> + '_ {
+        (&self.keys)
+            .iter()
+            .map(|index| K::from_index(u64::from(index)))
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.iter().map(|(_, value)| value)
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut V> {
+        self.iter_mut().map(|(_, value)| value)
+    }
+
+    /// Remove every entry, yielding them in key order. Allocated memory is kept for reuse.
+    pub fn drain(&mut self) -> Drain<'_, K, V> {
+        let keys = std::mem::replace(&mut self.keys, BitSet::new());
+        let len = std::mem::replace(&mut self.len, 0);
+        Drain {
+            keys: keys.iter(),
+            values: &mut self.values,
+            len,
+            _phantom_data: PhantomData,
+        }
+    }
+
+    /// Keep only the entries for which the predicate returns true
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&K, &mut V) -> bool,
+    {
+        for index in self.keys.clone() {
+            let key = K::from_index(u64::from(index));
+            let value = unsafe { self.values[index as usize].assume_init_mut() };
+            if !f(&key, value) {
+                self.remove(&key);
             }
         }
     }
 }
 
+// Private interface
+impl<K, V> SparseVecMap<K, V>
+where
+    K: StoreKey,
+{
+    fn slot(key: &K) -> usize
+    where
+        K: Copy,
+    {
+        let key = u32_index(key);
+        key as usize
+    }
+
+    fn drop_values(&mut self) {
+        // Forget the occupied indices before dropping, so a panicking drop leaks the remaining values instead of dropping them twice
+        let keys = std::mem::replace(&mut self.keys, BitSet::new());
+        self.len = 0;
+        for index in &keys {
+            unsafe { self.values[index as usize].assume_init_drop() };
+        }
+
+        self.values.clear();
+    }
+}
+
+impl<K, V> Index<&K> for SparseVecMap<K, V>
+where
+    K: StoreKey,
+{
+    type Output = V;
+
+    fn index(&self, key: &K) -> &V {
+        self.get(key).expect("Key not present in SparseVecMap")
+    }
+}
+
+impl<K, V> IndexMut<&K> for SparseVecMap<K, V>
+where
+    K: StoreKey,
+{
+    fn index_mut(&mut self, key: &K) -> &mut V {
+        self.get_mut(key).expect("Key not present in SparseVecMap")
+    }
+}
+
+impl<K, V> Extend<(K, V)> for SparseVecMap<K, V>
+where
+    K: StoreKey,
+{
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl<K, V> FromIterator<(K, V)> for SparseVecMap<K, V>
+where
+    K: StoreKey,
+{
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = SparseVecMap::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<K, V> IntoIterator for SparseVecMap<K, V>
+where
+    K: StoreKey,
+{
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V>;
+
+    fn into_iter(mut self) -> Self::IntoIter {
+        let keys = std::mem::replace(&mut self.keys, BitSet::new());
+        let len = std::mem::replace(&mut self.len, 0);
+        IntoIter {
+            keys: keys.iter(),
+            values: std::mem::take(&mut self.values),
+            len,
+            _phantom_data: PhantomData,
+        }
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a SparseVecMap<K, V>
+where
+    K: StoreKey,
+{
+    type Item = (K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a mut SparseVecMap<K, V>
+where
+    K: StoreKey,
+{
+    type Item = (K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+/// Iterator over the entries of a SparseVecMap in key order
+pub struct Iter<'a, K, V> {
+    keys: BitIter<&'a BitSet>,
+    values: &'a [MaybeUninit<V>],
+    len: usize,
+    _phantom_data: PhantomData<K>,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V>
+where
+    K: StoreKey,
+{
+    type Item = (K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let index = self.keys.next()?;
+        self.len -= 1;
+        let value = unsafe { self.values[index as usize].assume_init_ref() };
+        Some((K::from_index(u64::from(index)), value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, K, V> ExactSizeIterator for Iter<'a, K, V> where K: StoreKey {}
+
+/// Mutable iterator over the entries of a SparseVecMap in key order
+pub struct IterMut<'a, K, V> {
+    keys: BitIter<&'a BitSet>,
+    values: *mut MaybeUninit<V>,
+    len: usize,
+    _phantom_data: PhantomData<(K, &'a mut V)>,
+}
+
+impl<'a, K, V> Iterator for IterMut<'a, K, V>
+where
+    K: StoreKey,
+{
+    type Item = (K, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let index = self.keys.next()?;
+        self.len -= 1;
+        // Each occupied index is yielded once, so the returned references never alias
+        let value = unsafe { (*self.values.add(index as usize)).assume_init_mut() };
+        Some((K::from_index(u64::from(index)), value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, K, V> ExactSizeIterator for IterMut<'a, K, V> where K: StoreKey {}
+
+/// Owning iterator over the entries of a SparseVecMap in key order
+pub struct IntoIter<K, V> {
+    keys: BitIter<BitSet>,
+    values: Vec<MaybeUninit<V>>,
+    len: usize,
+    _phantom_data: PhantomData<K>,
+}
+
+impl<K, V> Iterator for IntoIter<K, V>
+where
+    K: StoreKey,
+{
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let index = self.keys.next()?;
+        self.len -= 1;
+        let value = unsafe { self.values[index as usize].assume_init_read() };
+        Some((K::from_index(u64::from(index)), value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<K, V> ExactSizeIterator for IntoIter<K, V> where K: StoreKey {}
+
+impl<K, V> Drop for IntoIter<K, V> {
+    fn drop(&mut self) {
+        for index in &mut self.keys {
+            unsafe { self.values[index as usize].assume_init_drop() };
+        }
+    }
+}
+
+/// Draining iterator over the entries of a SparseVecMap in key order
+pub struct Drain<'a, K, V> {
+    keys: BitIter<BitSet>,
+    values: &'a mut Vec<MaybeUninit<V>>,
+    len: usize,
+    _phantom_data: PhantomData<K>,
+}
+
+impl<'a, K, V> Iterator for Drain<'a, K, V>
+where
+    K: StoreKey,
+{
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let index = self.keys.next()?;
+        self.len -= 1;
+        let value = unsafe { self.values[index as usize].assume_init_read() };
+        Some((K::from_index(u64::from(index)), value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, K, V> ExactSizeIterator for Drain<'a, K, V> where K: StoreKey {}
+
+impl<'a, K, V> Drop for Drain<'a, K, V> {
+    fn drop(&mut self) {
+        for index in &mut self.keys {
+            unsafe { self.values[index as usize].assume_init_drop() };
+        }
+        self.values.clear();
+    }
+}
+
+/// A view into a single key of a SparseVecMap, which may be occupied or vacant
+pub enum Entry<'a, K, V>
+where
+    K: StoreKey,
+{
+    Occupied(OccupiedEntry<'a, K, V>),
+    Vacant(VacantEntry<'a, K, V>),
+}
+
+impl<'a, K, V> Entry<'a, K, V>
+where
+    K: StoreKey,
+{
+    pub fn key(&self) -> K {
+        match self {
+            Entry::Occupied(entry) => entry.key(),
+            Entry::Vacant(entry) => entry.key(),
+        }
+    }
+
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        self.or_insert_with(|| default)
+    }
+
+    pub fn or_insert_with<F>(self, default: F) -> &'a mut V
+    where
+        F: FnOnce() -> V,
+    {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    pub fn or_default(self) -> &'a mut V
+    where
+        V: Default,
+    {
+        self.or_insert_with(V::default)
+    }
+
+    pub fn and_modify<F>(mut self, f: F) -> Self
+    where
+        F: FnOnce(&mut V),
+    {
+        if let Entry::Occupied(entry) = &mut self {
+            f(entry.get_mut());
+        }
+        self
+    }
+}
+
+pub struct OccupiedEntry<'a, K, V>
+where
+    K: StoreKey,
+{
+    map: &'a mut SparseVecMap<K, V>,
+    key: K,
+}
+
+impl<'a, K, V> OccupiedEntry<'a, K, V>
+where
+    K: StoreKey,
+{
+    pub fn key(&self) -> K {
+        self.key
+    }
+
+    pub fn get(&self) -> &V {
+        &self.map[&self.key]
+    }
+
+    pub fn get_mut(&mut self) -> &mut V {
+        &mut self.map[&self.key]
+    }
+
+    pub fn into_mut(self) -> &'a mut V {
+        &mut self.map[&self.key]
+    }
+
+    /// Replace the entry's value, returning the old one
+    pub fn insert(&mut self, value: V) -> V {
+        std::mem::replace(self.get_mut(), value)
+    }
+
+    pub fn remove(self) -> V {
+        self.remove_entry().1
+    }
+
+    pub fn remove_entry(self) -> (K, V) {
+        let value = self.map.remove(&self.key).unwrap();
+        (self.key, value)
+    }
+}
+
+pub struct VacantEntry<'a, K, V>
+where
+    K: StoreKey,
+{
+    map: &'a mut SparseVecMap<K, V>,
+    key: K,
+}
+
+impl<'a, K, V> VacantEntry<'a, K, V>
+where
+    K: StoreKey,
+{
+    pub fn key(&self) -> K {
+        self.key
+    }
+
+    pub fn insert(self, value: V) -> &'a mut V {
+        self.map.insert(self.key, value);
+        &mut self.map[&self.key]
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::rc::Rc;
 
     #[test]
     fn insert() {
@@ -123,13 +591,17 @@ mod tests {
 
         let mut vec_map: SparseVecMap<u32, i32> = SparseVecMap::new();
 
-        vec_map.insert(0, 0, false);
-        vec_map.insert(2, 1, false);
-        vec_map.insert(4, 2, false);
-        vec_map.insert(6, 3, false);
-        vec_map.insert(8, 4, false);
+        assert_eq!(vec_map.insert(0, 0), None);
+        assert_eq!(vec_map.insert(2, 1), None);
+        assert_eq!(vec_map.insert(4, 2), None);
+        assert_eq!(vec_map.insert(6, 3), None);
+        assert_eq!(vec_map.insert(8, 4), None);
+        assert_eq!(vec_map.insert(2, 5), Some(1));
 
         println!("{:#?}\n", vec_map);
+
+        assert_eq!(vec_map.get(&2), Some(&5));
+        assert_eq!(vec_map.get(&3), None);
     }
 
     #[test]
@@ -138,20 +610,20 @@ mod tests {
 
         let mut vec_map: SparseVecMap<u32, i32> = SparseVecMap::new();
 
-        vec_map.insert(0, 0, false);
-        vec_map.insert(2, 1, false);
+        vec_map.insert(0, 0);
+        vec_map.insert(2, 1);
         println!("{:#?}\n", vec_map);
 
-        vec_map.remove(&0);
+        assert_eq!(vec_map.remove(&0), Some(0));
         println!("{:#?}\n", vec_map);
 
-        vec_map.remove(&1);
+        assert_eq!(vec_map.remove(&1), None);
         println!("{:#?}\n", vec_map);
 
-        vec_map.remove(&2);
+        assert_eq!(vec_map.remove(&2), Some(1));
         println!("{:#?}\n", vec_map);
 
-        vec_map.remove(&3);
+        assert_eq!(vec_map.remove(&3), None);
         println!("{:#?}\n", vec_map);
     }
 
@@ -161,16 +633,18 @@ mod tests {
 
         let mut vec_map: SparseVecMap<u32, i32> = SparseVecMap::new();
 
-        vec_map.insert(0, 0, false);
-        vec_map.insert(1, 1, false);
-        vec_map.insert(2, 2, false);
-        vec_map.insert(3, 3, false);
+        vec_map.insert(0, 0);
+        vec_map.insert(1, 1);
+        vec_map.insert(2, 2);
+        vec_map.insert(3, 3);
 
         println!("{:#?}\n", vec_map);
 
         vec_map.clear();
 
         println!("{:#?}\n", vec_map);
+
+        assert_eq!(vec_map.get(&0), None);
     }
 
     #[test]
@@ -181,10 +655,10 @@ mod tests {
 
         println!("{:#?}\n", vec_map);
 
-        vec_map.insert(0, 1, false);
-        vec_map.insert(1, 1, false);
-        vec_map.insert(2, 1, false);
-        vec_map.insert(3, 3, false);
+        vec_map.insert(0, 1);
+        vec_map.insert(1, 1);
+        vec_map.insert(2, 1);
+        vec_map.insert(3, 3);
 
         println!(
             "0: {:?}\n1: {:?}\n2: {:?}\n3: {:?}",
@@ -203,15 +677,155 @@ mod tests {
 
         println!("{:#?}\n", vec_map);
 
-        vec_map.insert(0, 0, false);
-        vec_map.insert(1, 1, false);
-        vec_map.insert(2, 2, false);
-        vec_map.insert(3, 3, false);
+        vec_map.insert(0, 0);
+        vec_map.insert(1, 1);
+        vec_map.insert(2, 2);
+        vec_map.insert(3, 3);
 
         println!("0: {:?}", vec_map.get_mut(&0));
         println!("1: {:?}", vec_map.get_mut(&1));
         println!("2: {:?}", vec_map.get_mut(&2));
         println!("3: {:?}", vec_map.get_mut(&3));
         println!();
+
+        *vec_map.get_mut(&2).unwrap() = 4;
+        assert_eq!(vec_map.get(&2), Some(&4));
+        assert_eq!(vec_map.get_mut(&4), None);
+    }
+
+    #[test]
+    fn drop_values() {
+        let value = Rc::new(());
+
+        let mut vec_map: SparseVecMap<u32, Rc<()>> = SparseVecMap::new();
+        vec_map.insert(0, value.clone());
+        vec_map.insert(3, value.clone());
+        vec_map.insert(5, value.clone());
+        vec_map.insert(7, value.clone());
+        assert_eq!(Rc::strong_count(&value), 5);
+
+        vec_map.insert(3, value.clone());
+        assert_eq!(Rc::strong_count(&value), 5);
+
+        vec_map.remove(&0);
+        assert_eq!(Rc::strong_count(&value), 4);
+
+        vec_map.clear();
+        assert_eq!(Rc::strong_count(&value), 1);
+
+        vec_map.insert(1, value.clone());
+        vec_map.insert(9, value.clone());
+        assert_eq!(Rc::strong_count(&value), 3);
+
+        drop(vec_map);
+        assert_eq!(Rc::strong_count(&value), 1);
+    }
+
+    #[test]
+    fn drop_values_panic() {
+        #[derive(Debug)]
+        struct PanicOnDrop(Rc<()>);
+
+        impl Drop for PanicOnDrop {
+            fn drop(&mut self) {
+                if Rc::strong_count(&self.0) == 3 {
+                    panic!("PanicOnDrop");
+                }
+            }
+        }
+
+        let value = Rc::new(());
+
+        let mut vec_map: SparseVecMap<u32, PanicOnDrop> = SparseVecMap::new();
+        vec_map.insert(0, PanicOnDrop(value.clone()));
+        vec_map.insert(1, PanicOnDrop(value.clone()));
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| vec_map.clear()));
+        assert!(result.is_err());
+        println!("{:?}", vec_map);
+        assert!(vec_map.is_empty());
+        assert!(vec_map.get(&1).is_none());
+
+        // The value after the panic is leaked, and dropping the map must not revisit either value
+        drop(vec_map);
+        assert_eq!(Rc::strong_count(&value), 2);
+    }
+
+    #[test]
+    fn iter() {
+        println!();
+
+        let mut vec_map: SparseVecMap<u32, i32> =
+            vec![(4, 4), (1, 1), (7, 7)].into_iter().collect();
+        assert_eq!(vec_map.len(), 3);
+
+        for (_, value) in &mut vec_map {
+            *value *= 10;
+        }
+
+        println!("{:#?}\n", vec_map);
+
+        assert_eq!(
+            vec_map.iter().collect::<Vec<_>>(),
+            vec![(1, &10), (4, &40), (7, &70)]
+        );
+        assert_eq!(vec_map.keys().collect::<Vec<_>>(), vec![1, 4, 7]);
+        assert_eq!(vec_map.values().sum::<i32>(), 120);
+        assert_eq!(vec_map[&4], 40);
+
+        vec_map[&4] = 5;
+        assert_eq!(
+            vec_map.into_iter().collect::<Vec<_>>(),
+            vec![(1, 10), (4, 5), (7, 70)]
+        );
+    }
+
+    #[test]
+    fn entry() {
+        let mut vec_map: SparseVecMap<u32, i32> = SparseVecMap::new();
+
+        *vec_map.entry(3).or_insert(1) += 1;
+        *vec_map.entry(3).or_insert(1) += 1;
+        vec_map.entry(5).and_modify(|value| *value = 0).or_default();
+        vec_map.entry(5).and_modify(|value| *value = 9);
+
+        assert_eq!(vec_map.get(&3), Some(&3));
+        assert_eq!(vec_map.get(&5), Some(&9));
+
+        if let Entry::Occupied(entry) = vec_map.entry(3) {
+            assert_eq!(entry.remove_entry(), (3, 3));
+        }
+        assert_eq!(vec_map.len(), 1);
+    }
+
+    #[test]
+    fn retain_drain_shrink() {
+        let value = Rc::new(());
+
+        let mut vec_map: SparseVecMap<u32, Rc<()>> = SparseVecMap::new();
+        vec_map.extend((0..8).map(|key| (key, value.clone())));
+
+        vec_map.retain(|key, _| key % 2 == 0);
+        assert_eq!(vec_map.keys().collect::<Vec<_>>(), vec![0, 2, 4, 6]);
+        assert_eq!(Rc::strong_count(&value), 5);
+
+        vec_map.remove(&6);
+        vec_map.shrink_to_fit();
+        assert_eq!(vec_map.len(), 3);
+        assert!(vec_map.get(&4).is_some());
+
+        let mut drain = vec_map.drain();
+        assert_eq!(drain.next().map(|(key, _)| key), Some(0));
+        drop(drain);
+
+        assert!(vec_map.is_empty());
+        assert_eq!(vec_map.get(&2), None);
+        assert_eq!(Rc::strong_count(&value), 1);
+
+        vec_map.insert(3, value.clone());
+        let into_iter = vec_map.into_iter();
+        assert_eq!(into_iter.len(), 1);
+        drop(into_iter);
+        assert_eq!(Rc::strong_count(&value), 1);
     }
 }
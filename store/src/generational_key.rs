@@ -0,0 +1,135 @@
+use std::convert::TryFrom;
+
+use hibitset::BitSet;
+
+use crate::StoreKey;
+
+/// Key pairing an index with a generation.
+/// Removing a key via Store::remove_key advances its index's generation, so old handles stop resolving instead of aliasing a key that later reuses the index.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct GenerationalKey {
+    index: u32,
+    generation: u32,
+}
+
+impl GenerationalKey {
+    pub fn new(index: u32, generation: u32) -> Self {
+        GenerationalKey { index, generation }
+    }
+
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+}
+
+impl From<u32> for GenerationalKey {
+    fn from(index: u32) -> Self {
+        GenerationalKey::new(index, 0)
+    }
+}
+
+impl From<GenerationalKey> for u32 {
+    fn from(key: GenerationalKey) -> Self {
+        key.index
+    }
+}
+
+impl StoreKey for GenerationalKey {
+    type KeySet = BitSet;
+
+    const GENERATIONAL: bool = true;
+
+    fn to_index(&self) -> u64 {
+        u64::from(self.index)
+    }
+
+    fn from_index(index: u64) -> Self {
+        GenerationalKey::from_parts(index, 0)
+    }
+
+    fn from_parts(index: u64, generation: u32) -> Self {
+        let index = u32::try_from(index).expect("Key index exceeds the u32 key range");
+        GenerationalKey::new(index, generation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Ref;
+
+    use super::*;
+    use crate::{StorageType, Store, StoreQuery};
+
+    #[test]
+    fn stale_keys() {
+        let mut store = Store::<GenerationalKey>::default();
+        store.register_storage_type_for::<String>(StorageType::BTreeMap);
+
+        let first = store.spawn((String::from("first"), 1));
+        let second = store.spawn((String::from("second"), 2));
+        store.remove_key(&first);
+
+        let reused = store.spawn((String::from("reused"), 3));
+        println!("{:?} {:?} {:?}", first, second, reused);
+        assert_eq!(reused.index(), first.index());
+        assert_eq!(reused.generation(), first.generation() + 1);
+
+        assert!(!store.is_current(&first));
+        assert!(store.get::<String>(&first).is_none());
+        assert!(store.get_mut::<i32>(&first).is_none());
+        assert!(!store.contains_key(&first));
+        assert!(store.types_of(&first).is_empty());
+        assert_eq!(store.iter_key_untyped(&first).count(), 0);
+
+        // Removing through a stale handle leaves the key that reused its index alone
+        store.remove::<i32>(&first);
+        store.remove_key(&first);
+        assert_eq!(*store.get::<i32>(&reused).unwrap(), 3);
+        assert_eq!(&*store.get::<String>(&reused).unwrap(), "reused");
+
+        // Iteration yields current handles
+        let keys: Vec<GenerationalKey> =
+            StoreQuery::<(GenerationalKey, Ref<String>, Ref<i32>)>::iter(&store)
+                .map(|(key, _, _)| key)
+                .collect();
+        assert_eq!(keys, vec![reused, second]);
+
+        let keys: Vec<GenerationalKey> = store.range::<String, _>(..).map(|(key, _)| key).collect();
+        assert_eq!(keys, vec![reused, second]);
+
+        let keys: Vec<GenerationalKey> =
+            StoreQuery::<(GenerationalKey, Ref<i32>)>::iter_keys(&store, &[first, second])
+                .map(|(key, _)| key)
+                .collect();
+        assert_eq!(keys, vec![second]);
+
+        let strings = store.storage::<String>().unwrap();
+        assert!(strings.get(&first).is_none());
+        assert_eq!(strings.keys().collect::<Vec<_>>(), vec![reused, second]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn stale_query() {
+        let mut store = Store::<GenerationalKey>::default();
+        let key = store.spawn((1,));
+        store.remove_key(&key);
+        store.spawn((2,));
+
+        StoreQuery::<(GenerationalKey, Option<Ref<i32>>)>::get(&store, &key);
+    }
+
+    #[test]
+    #[should_panic]
+    fn stale_insert() {
+        let mut store = Store::<GenerationalKey>::default();
+        let key = store.spawn((1,));
+        store.remove_key(&key);
+
+        store.insert(key, 2);
+    }
+}
@@ -0,0 +1,80 @@
+use crate::{key_map::KeyMap, HashMap, StoreKey};
+
+/// Bidirectional registry of names for keys, holding index-only keys.
+/// Names are unique, while a key may hold several names in the order they were registered.
+#[derive(Debug)]
+pub(crate) struct KeyAliases<Key>
+where
+    Key: StoreKey,
+{
+    keys: HashMap<String, Key>,
+    aliases: KeyMap<Key, Vec<String>>,
+}
+
+impl<Key> Default for KeyAliases<Key>
+where
+    Key: StoreKey,
+{
+    fn default() -> Self {
+        KeyAliases {
+            keys: HashMap::default(),
+            aliases: KeyMap::default(),
+        }
+    }
+}
+
+impl<Key> KeyAliases<Key>
+where
+    Key: StoreKey,
+{
+    pub fn contains_key(&self, key: &Key) -> bool {
+        self.aliases.contains_key(key)
+    }
+
+    pub fn lookup(&self, name: &str) -> Option<Key> {
+        self.keys.get(name).copied()
+    }
+
+    pub fn aliases_of(&self, key: &Key) -> &[String] {
+        self.aliases.get(key).map_or(&[], Vec::as_slice)
+    }
+
+    /// Point a name at a key, returning the key it previously named
+    pub fn alias(&mut self, key: Key, name: String) -> Option<Key> {
+        let previous = self.unalias(&name);
+
+        if !self.aliases.contains_key(&key) {
+            self.aliases.insert(key, Vec::new());
+        }
+        self.aliases.get_mut(&key).unwrap().push(name.clone());
+        self.keys.insert(name, key);
+
+        previous
+    }
+
+    /// Forget a name, returning the key it named
+    pub fn unalias(&mut self, name: &str) -> Option<Key> {
+        let key = self.keys.remove(name)?;
+
+        let names = self.aliases.get_mut(&key).unwrap();
+        names.retain(|candidate| candidate != name);
+        if names.is_empty() {
+            self.aliases.remove(&key);
+        }
+
+        Some(key)
+    }
+
+    /// Forget every name of a key, returning them
+    pub fn remove_key(&mut self, key: &Key) -> Vec<String> {
+        let names = self.aliases.remove(key).unwrap_or_default();
+        for name in &names {
+            self.keys.remove(name);
+        }
+        names
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = Key> + '_ {
+        self.aliases.keys()
+    }
+}
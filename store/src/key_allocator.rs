@@ -0,0 +1,133 @@
+use std::cell::{Cell, RefCell};
+
+use crate::{key_map::KeyMap, KeySet, KeySetOf, StoreKey};
+
+/// Hands out unused key indices through a shared reference, reusing indices once they are released.
+/// For generational key types, also tracks the live generation of each released index, against which handles are resolved.
+#[derive(Debug)]
+pub(crate) struct KeyAllocator<Key>
+where
+    Key: StoreKey,
+{
+    next: Cell<u64>,
+    free: RefCell<Vec<u64>>,
+    /// Indices currently in `free`, so an index released again before reuse is not queued twice
+    free_set: RefCell<KeySetOf<Key>>,
+    generations: KeyMap<Key, u32>,
+}
+
+impl<Key> Default for KeyAllocator<Key>
+where
+    Key: StoreKey,
+{
+    fn default() -> Self {
+        KeyAllocator {
+            next: Default::default(),
+            free: Default::default(),
+            free_set: Default::default(),
+            generations: Default::default(),
+        }
+    }
+}
+
+impl<Key> KeyAllocator<Key>
+where
+    Key: StoreKey,
+{
+    /// Reuse the most recently released index, or take the next unallocated one, skipping any for which `in_use` holds
+    pub fn allocate<F>(&self, in_use: F) -> u64
+    where
+        F: Fn(u64) -> bool,
+    {
+        while let Some(index) = self.free.borrow_mut().pop() {
+            self.free_set.borrow_mut().remove(&Key::from_index(index));
+            if !in_use(index) {
+                return index;
+            }
+        }
+
+        loop {
+            let index = self.next.get();
+            self.next
+                .set(index.checked_add(1).expect("Key space exhausted"));
+            if !in_use(index) {
+                return index;
+            }
+        }
+    }
+
+    /// Whether an index was handed out by allocate and has not been released since
+    pub fn is_allocated(&self, index: u64) -> bool {
+        index < self.next.get() && !self.free_set.borrow().contains(&Key::from_index(index))
+    }
+
+    /// Invalidate existing handles to an index, returning it for reuse if it was allocated
+    pub fn release(&mut self, index: u64) {
+        if Key::GENERATIONAL {
+            let generation = self.generation(index).wrapping_add(1);
+            self.generations.insert(Key::from_index(index), generation);
+        }
+
+        if index < self.next.get() && self.free_set.get_mut().insert(&Key::from_index(index)) {
+            self.free.get_mut().push(index);
+        }
+    }
+
+    pub fn generation(&self, index: u64) -> u32 {
+        if !Key::GENERATIONAL {
+            return 0;
+        }
+        self.generations
+            .get(&Key::from_index(index))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// The handle for an index at its live generation
+    pub fn current(&self, index: u64) -> Key {
+        Key::from_parts(index, self.generation(index))
+    }
+
+    /// The index-only key used to address storage, or None if the handle's generation is no longer live
+    pub fn resolve(&self, key: &Key) -> Option<Key> {
+        if !Key::GENERATIONAL {
+            return Some(*key);
+        }
+
+        let index = key.to_index();
+        if *key == self.current(index) {
+            Some(Key::from_index(index))
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::GenerationalKey;
+
+    #[test]
+    fn generations() {
+        let mut allocator = KeyAllocator::<u64>::default();
+        for _ in 0..100 {
+            let index = allocator.allocate(|_| false);
+            allocator.release(index);
+        }
+        println!("{:?}", allocator);
+        assert_eq!(allocator.generations.keys().count(), 0);
+        assert_eq!(allocator.resolve(&7), Some(7));
+
+        let mut allocator = KeyAllocator::<GenerationalKey>::default();
+        let index = allocator.allocate(|_| false);
+        let key = allocator.current(index);
+        allocator.release(index);
+        assert_eq!(allocator.generation(index), 1);
+        assert_eq!(allocator.resolve(&key), None);
+        assert_eq!(
+            allocator.resolve(&allocator.current(index)),
+            Some(GenerationalKey::from_index(index))
+        );
+    }
+}
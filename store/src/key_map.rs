@@ -0,0 +1,71 @@
+use crate::{is_wide, HashMap, PagedSparseVecMap, StoreKey};
+
+/// Per-key bookkeeping map, paged by index for key types that fit u32 and hashed for wider key spaces
+#[derive(Debug)]
+pub(crate) enum KeyMap<Key, V>
+where
+    Key: StoreKey,
+{
+    Paged(PagedSparseVecMap<Key, V>),
+    Hashed(HashMap<Key, V>),
+}
+
+impl<Key, V> Default for KeyMap<Key, V>
+where
+    Key: StoreKey,
+{
+    fn default() -> Self {
+        if is_wide::<Key>() {
+            KeyMap::Hashed(HashMap::default())
+        } else {
+            KeyMap::Paged(PagedSparseVecMap::new())
+        }
+    }
+}
+
+impl<Key, V> KeyMap<Key, V>
+where
+    Key: StoreKey,
+{
+    pub fn contains_key(&self, key: &Key) -> bool {
+        match self {
+            KeyMap::Paged(map) => map.contains_key(key),
+            KeyMap::Hashed(map) => map.contains_key(key),
+        }
+    }
+
+    pub fn get(&self, key: &Key) -> Option<&V> {
+        match self {
+            KeyMap::Paged(map) => map.get(key),
+            KeyMap::Hashed(map) => map.get(key),
+        }
+    }
+
+    pub fn get_mut(&mut self, key: &Key) -> Option<&mut V> {
+        match self {
+            KeyMap::Paged(map) => map.get_mut(key),
+            KeyMap::Hashed(map) => map.get_mut(key),
+        }
+    }
+
+    pub fn insert(&mut self, key: Key, value: V) -> Option<V> {
+        match self {
+            KeyMap::Paged(map) => map.insert(key, value),
+            KeyMap::Hashed(map) => map.insert(key, value),
+        }
+    }
+
+    pub fn remove(&mut self, key: &Key) -> Option<V> {
+        match self {
+            KeyMap::Paged(map) => map.remove(key),
+            KeyMap::Hashed(map) => map.remove(key),
+        }
+    }
+
+    pub fn keys(&self) -> Box<dyn Iterator<Item = Key> + '_> {
+        match self {
+            KeyMap::Paged(map) => Box::new(map.keys()),
+            KeyMap::Hashed(map) => Box::new(map.keys().copied()),
+        }
+    }
+}
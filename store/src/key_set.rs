@@ -0,0 +1,347 @@
+use std::{fmt::Debug, ops::Bound};
+
+use hibitset::{BitIter, BitSet, BitSetLike};
+
+use crate::{u32_index, KeyRange, KeySetOf, RoaringIntoIter, RoaringIter, RoaringSet, StoreKey};
+
+/// Set of keys recording which keys hold a value, addressed by key index
+pub trait KeySet<Key>: Debug + Default + Clone + PartialEq {
+    type Iter<'a>: Iterator<Item = Key>
+    where
+        Self: 'a;
+    type IntoIter: Iterator<Item = Key>;
+
+    /// Largest key index the set can hold
+    const MAX_INDEX: u64;
+
+    /// Returns true if the key was not already present
+    fn insert(&mut self, key: &Key) -> bool;
+    /// Returns true if the key was present
+    fn remove(&mut self, key: &Key) -> bool;
+    fn contains(&self, key: &Key) -> bool;
+    fn is_empty(&self) -> bool;
+    fn len(&self) -> usize;
+    fn clear(&mut self);
+
+    /// Keys in ascending index order
+    fn iter(&self) -> Self::Iter<'_>;
+    fn into_keys(self) -> Self::IntoIter;
+
+    fn union_with(&mut self, other: &Self);
+    fn intersect_with(&mut self, other: &Self);
+    fn difference_with(&mut self, other: &Self);
+
+    /// Keys that fall within a range, in ascending order
+    fn range(&self, range: &KeyRange<Key>) -> Vec<Key>
+    where
+        Key: StoreKey,
+    {
+        self.iter()
+            .skip_while(|key| match range.0 {
+                Bound::Included(start) => *key < start,
+                Bound::Excluded(start) => *key <= start,
+                Bound::Unbounded => false,
+            })
+            .take_while(|key| match range.1 {
+                Bound::Included(end) => *key <= end,
+                Bound::Excluded(end) => *key < end,
+                Bound::Unbounded => true,
+            })
+            .collect()
+    }
+}
+
+/// Keys returned by Store::keys and Store::keys_all, iterated in ascending index order
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keys<Key>(KeySetOf<Key>)
+where
+    Key: StoreKey;
+
+impl<Key> Keys<Key>
+where
+    Key: StoreKey,
+{
+    pub(crate) fn new(keys: KeySetOf<Key>) -> Self {
+        Keys(keys)
+    }
+
+    pub(crate) fn into_key_set(self) -> KeySetOf<Key> {
+        self.0
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn contains(&self, key: &Key) -> bool {
+        self.0.contains(key)
+    }
+
+    pub fn iter(&self) -> <KeySetOf<Key> as KeySet<Key>>::Iter<'_> {
+        self.0.iter()
+    }
+}
+
+impl<Key> IntoIterator for Keys<Key>
+where
+    Key: StoreKey,
+{
+    type Item = Key;
+    type IntoIter = <KeySetOf<Key> as KeySet<Key>>::IntoIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_keys()
+    }
+}
+
+impl<'a, Key> IntoIterator for &'a Keys<Key>
+where
+    Key: StoreKey,
+{
+    type Item = Key;
+    type IntoIter = <KeySetOf<Key> as KeySet<Key>>::Iter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl<Key> KeySet<Key> for BitSet
+where
+    Key: StoreKey,
+{
+    type Iter<'a> = std::iter::Map<BitIter<&'a BitSet>, fn(u32) -> Key>;
+    type IntoIter = std::iter::Map<BitIter<BitSet>, fn(u32) -> Key>;
+
+    // hibitset's four 64-bit layers address 64^4 indices
+    const MAX_INDEX: u64 = (1 << 24) - 1;
+
+    fn insert(&mut self, key: &Key) -> bool {
+        let index = bit_set_index(key).unwrap_or_else(|| {
+            panic!(
+                "Key index {} exceeds the BitSet key range of {}, use a u64 key type",
+                key.to_index(),
+                <BitSet as KeySet<Key>>::MAX_INDEX
+            )
+        });
+        !BitSet::add(self, index)
+    }
+
+    fn remove(&mut self, key: &Key) -> bool {
+        bit_set_index(key).is_some_and(|index| BitSet::remove(self, index))
+    }
+
+    fn contains(&self, key: &Key) -> bool {
+        bit_set_index(key).is_some_and(|index| BitSet::contains(self, index))
+    }
+
+    fn is_empty(&self) -> bool {
+        BitSetLike::is_empty(self)
+    }
+
+    fn len(&self) -> usize {
+        BitSetLike::iter(self).count()
+    }
+
+    fn clear(&mut self) {
+        BitSet::clear(self)
+    }
+
+    fn iter(&self) -> Self::Iter<'_> {
+        BitSetLike::iter(self).map(|index| Key::from_index(u64::from(index)))
+    }
+
+    fn into_keys(self) -> Self::IntoIter {
+        self.into_iter()
+            .map(|index| Key::from_index(u64::from(index)))
+    }
+
+    fn union_with(&mut self, other: &Self) {
+        *self |= other;
+    }
+
+    fn intersect_with(&mut self, other: &Self) {
+        *self &= other;
+    }
+
+    fn difference_with(&mut self, other: &Self) {
+        *self &= &!other;
+    }
+
+    fn range(&self, range: &KeyRange<Key>) -> Vec<Key>
+    where
+        Key: StoreKey,
+    {
+        let start = match range.0 {
+            Bound::Included(start) => start.to_index(),
+            Bound::Excluded(start) => start.to_index().saturating_add(1),
+            Bound::Unbounded => 0,
+        };
+        let before_end = |index: u64| match range.1 {
+            Bound::Included(end) => index <= end.to_index(),
+            Bound::Excluded(end) => index < end.to_index(),
+            Bound::Unbounded => true,
+        };
+
+        // Seek from the start of the range, so the work is bounded by the keys it holds rather than the set
+        let mut keys = vec![];
+        let mut index = start;
+        while index <= <BitSet as KeySet<Key>>::MAX_INDEX {
+            match next_bit(self, 0, index as usize) {
+                Some(found) if before_end(found as u64) => {
+                    keys.push(Key::from_index(found as u64));
+                    index = found as u64 + 1;
+                }
+                _ => break,
+            }
+        }
+        keys
+    }
+}
+
+/// Index of a key within a BitSet, or None if it lies beyond the set's range
+fn bit_set_index<Key>(key: &Key) -> Option<u32>
+where
+    Key: StoreKey,
+{
+    if key.to_index() <= <BitSet as KeySet<Key>>::MAX_INDEX {
+        Some(u32_index(key))
+    } else {
+        None
+    }
+}
+
+/// Index of the first set bit at or after `index` in a layer of a BitSet, skipping empty words through the layers above
+fn next_bit(set: &BitSet, layer: usize, index: usize) -> Option<usize> {
+    const SHIFT: u32 = usize::BITS.trailing_zeros();
+
+    let word_at = |word_index: usize| match layer {
+        0 => set.layer0(word_index),
+        1 => set.layer1(word_index),
+        2 => set.layer2(word_index),
+        _ if word_index == 0 => set.layer3(),
+        _ => 0,
+    };
+
+    let word_index = index >> SHIFT;
+    let word = word_at(word_index) & (usize::MAX << (index & (usize::BITS as usize - 1)));
+    if word != 0 {
+        return Some((word_index << SHIFT) | word.trailing_zeros() as usize);
+    }
+    if layer == 3 {
+        return None;
+    }
+
+    let word_index = next_bit(set, layer + 1, word_index + 1)?;
+    Some((word_index << SHIFT) | word_at(word_index).trailing_zeros() as usize)
+}
+
+impl<Key> KeySet<Key> for RoaringSet
+where
+    Key: StoreKey,
+{
+    type Iter<'a> = std::iter::Map<RoaringIter<'a>, fn(u64) -> Key>;
+    type IntoIter = std::iter::Map<RoaringIntoIter, fn(u64) -> Key>;
+
+    const MAX_INDEX: u64 = u64::MAX;
+
+    fn insert(&mut self, key: &Key) -> bool {
+        RoaringSet::insert(self, key.to_index())
+    }
+
+    fn remove(&mut self, key: &Key) -> bool {
+        RoaringSet::remove(self, key.to_index())
+    }
+
+    fn contains(&self, key: &Key) -> bool {
+        RoaringSet::contains(self, key.to_index())
+    }
+
+    fn is_empty(&self) -> bool {
+        RoaringSet::is_empty(self)
+    }
+
+    fn len(&self) -> usize {
+        RoaringSet::len(self)
+    }
+
+    fn clear(&mut self) {
+        RoaringSet::clear(self)
+    }
+
+    fn iter(&self) -> Self::Iter<'_> {
+        RoaringSet::iter(self).map(Key::from_index)
+    }
+
+    fn into_keys(self) -> Self::IntoIter {
+        self.into_iter().map(Key::from_index)
+    }
+
+    fn union_with(&mut self, other: &Self) {
+        RoaringSet::union_with(self, other)
+    }
+
+    fn intersect_with(&mut self, other: &Self) {
+        RoaringSet::intersect_with(self, other)
+    }
+
+    fn difference_with(&mut self, other: &Self) {
+        RoaringSet::difference_with(self, other)
+    }
+
+    fn range(&self, range: &KeyRange<Key>) -> Vec<Key> {
+        let index = |bound: Bound<Key>| bound.map(|key| key.to_index());
+        RoaringSet::range(self, (index(range.0), index(range.1)))
+            .map(Key::from_index)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ops::RangeBounds;
+
+    #[test]
+    fn bit_set_range() {
+        let indices = [
+            0u32,
+            5,
+            63,
+            64,
+            700,
+            4095,
+            4096,
+            262_143,
+            262_144,
+            (1 << 24) - 1,
+        ];
+        let mut set = BitSet::new();
+        for index in &indices {
+            set.add(*index);
+        }
+
+        let ranges: [KeyRange<u32>; 6] = [
+            (Bound::Unbounded, Bound::Unbounded),
+            (Bound::Included(5), Bound::Excluded(4096)),
+            (Bound::Excluded(64), Bound::Included(262_144)),
+            (Bound::Included(701), Bound::Excluded(4095)),
+            (Bound::Excluded(262_144), Bound::Unbounded),
+            (Bound::Included(1 << 20), Bound::Included(1 << 21)),
+        ];
+        for range in &ranges {
+            let keys: Vec<u32> = KeySet::<u32>::range(&set, range);
+            let expected: Vec<u32> = indices
+                .iter()
+                .copied()
+                .filter(|index| range.contains(index))
+                .collect();
+            println!("{:?}: {:?}", range, keys);
+            assert_eq!(keys, expected);
+        }
+    }
+}
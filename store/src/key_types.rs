@@ -0,0 +1,54 @@
+use crate::{key_map::KeyMap, StoreKey, TypeKey};
+
+/// Index of the types held by each key, kept sorted by TypeKey
+#[derive(Debug)]
+pub(crate) struct KeyTypes<Key>(KeyMap<Key, Vec<TypeKey>>)
+where
+    Key: StoreKey;
+
+impl<Key> Default for KeyTypes<Key>
+where
+    Key: StoreKey,
+{
+    fn default() -> Self {
+        KeyTypes(KeyMap::default())
+    }
+}
+
+impl<Key> KeyTypes<Key>
+where
+    Key: StoreKey,
+{
+    pub fn contains_key(&self, key: &Key) -> bool {
+        self.0.contains_key(key)
+    }
+
+    pub fn types_of(&self, key: &Key) -> &[TypeKey] {
+        self.0.get(key).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn add(&mut self, key: Key, type_key: TypeKey) {
+        if !self.0.contains_key(&key) {
+            self.0.insert(key, Vec::new());
+        }
+
+        let types = self.0.get_mut(&key).unwrap();
+        if let Err(index) = types.binary_search(&type_key) {
+            types.insert(index, type_key);
+        }
+    }
+
+    pub fn remove(&mut self, key: &Key, type_key: &TypeKey) {
+        if let Some(types) = self.0.get_mut(key) {
+            types.retain(|candidate| candidate != type_key);
+            if types.is_empty() {
+                self.0.remove(key);
+            }
+        }
+    }
+
+    /// Forget a key entirely, returning the types it held
+    pub fn remove_key(&mut self, key: &Key) -> Vec<TypeKey> {
+        self.0.remove(key).unwrap_or_default()
+    }
+}
@@ -1,263 +1,2006 @@
+extern crate self as store;
+
+mod adaptive_storage;
+mod archetype;
 mod assemblage;
 mod collections;
+mod generational_key;
+mod key_aliases;
+mod key_allocator;
+mod key_map;
+mod key_set;
+mod key_types;
+mod scoped_key;
 mod storage;
+mod storage_view;
 mod store_backing;
 mod store_key;
 mod store_query;
 mod type_key;
 mod typed_data;
 
+pub use adaptive_storage::*;
+use archetype::Archetypes;
 pub use assemblage::*;
 pub use collections::*;
+pub use generational_key::*;
+use key_aliases::KeyAliases;
+use key_allocator::KeyAllocator;
+pub use key_set::*;
+use key_types::KeyTypes;
+pub use scoped_key::*;
 pub use storage::*;
+pub use storage_view::*;
 pub use store_backing::*;
 pub use store_key::*;
 pub use store_query::*;
 pub use type_key::*;
 pub use typed_data::*;
 
-use hibitset::BitSet;
-use std::{cell::Ref, cell::RefMut, fmt::Debug, hash::Hash};
+pub use store_macros::StoreKey;
+
+use std::{cell::Ref, cell::RefMut, fmt::Debug, hash::Hash, ops::RangeBounds};
+use store_query::{QuerySegment, StoreIteratorKeys};
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub enum StorageType {
     BTreeMap,
     HashMap,
     SparseVecMap,
+    PagedSparseVecMap,
+    DenseVecMap,
+    InsertionOrderedMap,
+    DefaultVecMap,
+    Unit,
+    Custom,
+}
+
+/// Component size thresholds used to automatically select a StorageType.
+///
+/// Values are held unboxed, so a large component occupies its full size in its storage and is moved by value
+/// on insert and on storage migration. Box very large components, e.g. as Box<[u8]>, to keep them off the stack.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct StorageThresholds {
+    /// Components smaller than this many bytes are stored in a SparseVecMap
+    pub sparse_vec_map: usize,
+    /// Components up to this many bytes are stored in a DenseVecMap, larger ones in a HashMap
+    pub dense_vec_map: usize,
+}
+
+impl Default for StorageThresholds {
+    fn default() -> Self {
+        StorageThresholds {
+            sparse_vec_map: std::mem::size_of::<usize>(),
+            // Common L2 cache size
+            dense_vec_map: 256 * 1024,
+        }
+    }
+}
+
+impl StorageType {
+    /// Whether the storage addresses values by key index, limiting it to keys whose indices fit u32
+    pub fn is_index_addressed(&self) -> bool {
+        matches!(
+            self,
+            StorageType::SparseVecMap
+                | StorageType::PagedSparseVecMap
+                | StorageType::DenseVecMap
+                | StorageType::InsertionOrderedMap
+                | StorageType::DefaultVecMap
+        )
+    }
+}
+
+impl StorageThresholds {
+    pub fn storage_type_for_size(&self, size: usize) -> StorageType {
+        if size == 0 {
+            StorageType::Unit
+        } else if size < self.sparse_vec_map {
+            StorageType::SparseVecMap
+        } else if size <= self.dense_vec_map {
+            StorageType::DenseVecMap
+        } else {
+            StorageType::HashMap
+        }
+    }
+}
+
+/// Constructor for a custom StorageTrait implementation registered against a type
+struct CustomStorage<Key>(Box<dyn Fn() -> StoreBacking<Key>>)
+where
+    Key: StoreKey + 'static;
+
+impl<Key> Debug for CustomStorage<Key>
+where
+    Key: StoreKey + 'static,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("CustomStorage")
+    }
 }
 
 /// Associative type-keyed storage
-#[derive(Debug, Default)]
+#[derive(Default)]
 pub struct Store<Key>
 where
-    Key: Debug + Copy + Eq + Ord + Hash + From<u32> + Into<u32> + 'static,
+    Key: StoreKey + 'static,
 {
     type_map: HashMap<TypeKey, StoreBacking<Key>>,
+    key_types: KeyTypes<Key>,
+    key_allocator: KeyAllocator<Key>,
+    key_aliases: KeyAliases<Key>,
+    disabled: KeySetOf<Key>,
+    storage_types: HashMap<TypeKey, StorageType>,
+    custom_storages: HashMap<TypeKey, CustomStorage<Key>>,
+    storage_thresholds: StorageThresholds,
+    adaptive_storage: Option<AdaptiveStorage>,
+    archetypes: Option<Archetypes<Key>>,
 }
 
 impl<Key> Store<Key>
 where
-    Key: Debug + Copy + Eq + Ord + Hash + From<u32> + Into<u32> + 'static,
+    Key: StoreKey + 'static,
 {
-    fn storage_type_of<T>() -> &'static StorageType
+    pub fn with_storage_thresholds(storage_thresholds: StorageThresholds) -> Self {
+        Store {
+            type_map: Default::default(),
+            key_types: Default::default(),
+            key_allocator: Default::default(),
+            key_aliases: Default::default(),
+            disabled: Default::default(),
+            storage_types: Default::default(),
+            custom_storages: Default::default(),
+            storage_thresholds,
+            adaptive_storage: None,
+            archetypes: None,
+        }
+    }
+
+    /// Create a store that groups keys by their exact set of types into column tables.
+    /// Queries walk only the tables holding the requested types, and values move between tables as types are added or removed.
+    /// Per-type storage selection does not apply in this mode.
+    pub fn with_archetypes() -> Self {
+        Store {
+            archetypes: Some(Archetypes::default()),
+            ..Store::with_storage_thresholds(StorageThresholds::default())
+        }
+    }
+
+    pub fn is_archetypal(&self) -> bool {
+        self.archetypes.is_some()
+    }
+
+    fn assert_not_archetypal(&self, feature: &str) {
+        assert!(
+            self.archetypes.is_none(),
+            "{} is not available in archetype mode",
+            feature
+        );
+    }
+
+    pub fn storage_thresholds(&self) -> &StorageThresholds {
+        &self.storage_thresholds
+    }
+
+    /// Replace the thresholds used to select storage for types that have not yet been inserted
+    pub fn set_storage_thresholds(&mut self, storage_thresholds: StorageThresholds) {
+        self.storage_thresholds = storage_thresholds;
+    }
+
+    fn storage_type_of<T>(&self) -> StorageType
     where
         T: 'static,
     {
-        if std::mem::size_of::<T>() < std::mem::size_of::<usize>() {
-            &StorageType::SparseVecMap
+        if let Some(storage_type) = self.storage_types.get(&TypeKey::of::<T>()) {
+            *storage_type
         } else {
-            &StorageType::HashMap
+            let storage_type = match self
+                .storage_thresholds
+                .storage_type_for_size(std::mem::size_of::<T>())
+            {
+                // Zero-sized types with drop glue are kept in real storage
+                StorageType::Unit if std::mem::needs_drop::<T>() => StorageType::SparseVecMap,
+                storage_type => storage_type,
+            };
+            Self::storage_type_for_key(storage_type)
         }
     }
-}
 
-impl<Key> Store<Key>
-where
-    Key: Debug + Copy + Eq + Ord + Hash + From<u32> + Into<u32> + 'static,
-{
-    pub fn get<T>(&self, key: &Key) -> Option<Ref<T>>
+    /// Fall back to HashMap where index-addressed storage cannot hold the full range of Key
+    fn storage_type_for_key(storage_type: StorageType) -> StorageType {
+        if is_wide::<Key>() && storage_type.is_index_addressed() {
+            StorageType::HashMap
+        } else {
+            storage_type
+        }
+    }
+
+    fn create_storage_for<T>(&self) -> StoreBacking<Key>
+    where
+        T: Debug + 'static,
+    {
+        if let Some(custom_storage) = self.custom_storages.get(&TypeKey::of::<T>()) {
+            (custom_storage.0)()
+        } else {
+            StoreBacking::new::<T>(self.storage_type_of::<T>())
+        }
+    }
+
+    fn assert_storage_type_valid_for<T>(storage_type: StorageType)
     where
         T: 'static,
     {
-        let type_key = TypeKey::of::<T>();
-        let type_backing = self.type_map.get(&type_key)?;
-        let data = type_backing.get(key)?;
-        Some(Ref::map(data, |data| data.downcast::<T>().unwrap()))
+        // DefaultVecMap needs T: Default, which a StorageType alone cannot require
+        assert!(
+            storage_type != StorageType::DefaultVecMap,
+            "DefaultVecMap storage must be registered via register_storage_for::<{0}, DefaultVecMap<_, {0}>>",
+            std::any::type_name::<T>()
+        );
+        assert!(
+            storage_type != StorageType::Custom,
+            "Custom storage must be registered via register_storage_for or migrate_storage_for"
+        );
+        assert!(
+            storage_type != StorageType::Unit || std::mem::size_of::<T>() == 0,
+            "Unit storage can only hold zero-sized types, {} is {} bytes",
+            std::any::type_name::<T>(),
+            std::mem::size_of::<T>()
+        );
+        assert!(
+            storage_type != StorageType::Unit || !std::mem::needs_drop::<T>(),
+            "Unit storage can only hold types without drop glue, {} needs drop",
+            std::any::type_name::<T>()
+        );
+        assert!(
+            !(is_wide::<Key>() && storage_type.is_index_addressed()),
+            "{:?} storage cannot hold {} keys, whose indices exceed u32",
+            storage_type,
+            std::any::type_name::<Key>()
+        );
     }
 
-    pub fn get_mut<T>(&self, key: &Key) -> Option<RefMut<T>>
+    /// Pin the storage used for type T. Must be called before T is first inserted.
+    pub fn register_storage_type_for<T>(&mut self, storage_type: StorageType)
     where
         T: 'static,
     {
+        self.assert_not_archetypal("Per-type storage selection");
+        Self::assert_storage_type_valid_for::<T>(storage_type);
+
         let type_key = TypeKey::of::<T>();
-        let type_backing = self.type_map.get(&type_key)?;
-        let data = type_backing.get_mut(key)?;
-        Some(RefMut::map(data, |data| data.downcast_mut::<T>().unwrap()))
+        assert!(
+            !self.type_map.contains_key(&type_key),
+            "register_storage_type_for() called after first insert of {}",
+            std::any::type_name::<T>()
+        );
+
+        self.custom_storages.remove(&type_key);
+        self.storage_types.insert(type_key, storage_type);
     }
 
-    fn create_storage_for<T>() -> StoreBacking<Key>
+    /// Pin a specific StorageTrait implementation as the storage for type T. Must be called before T is first inserted.
+    pub fn register_storage_for<T, S>(&mut self)
     where
-        T: 'static,
+        T: Debug + 'static,
+        S: StorageTrait<Key = Key, Value = T> + Default + 'static,
     {
-        match Self::storage_type_of::<T>() {
-            StorageType::BTreeMap => StoreBacking::BTreeMap(StoreBackingRefCell::default()),
-            StorageType::HashMap => StoreBacking::HashMap(StoreBackingRefCell::default()),
-            StorageType::SparseVecMap => StoreBacking::SparseVecMap(StoreBackingRefCell::default()),
-        }
+        self.assert_not_archetypal("Per-type storage selection");
+        let type_key = TypeKey::of::<T>();
+        assert!(
+            !self.type_map.contains_key(&type_key),
+            "register_storage_for() called after first insert of {}",
+            std::any::type_name::<T>()
+        );
+
+        self.storage_types.insert(type_key, StorageType::Custom);
+        self.custom_storages.insert(
+            type_key,
+            CustomStorage(Box::new(|| StoreBacking::with_storage(S::default()))),
+        );
     }
 
-    pub fn insert<T>(&mut self, key: Key, value: T)
+    /// Pin the storage used for type T, moving any existing keys and values into the new backing
+    pub fn migrate_storage_type_for<T>(&mut self, storage_type: StorageType)
     where
         T: Debug + 'static,
     {
-        let type_backing = self
-            .type_map
-            .entry(TypeKey::of::<T>())
-            .or_insert_with(Self::create_storage_for::<T>);
+        self.assert_not_archetypal("Per-type storage selection");
+        Self::assert_storage_type_valid_for::<T>(storage_type);
+
+        let type_key = TypeKey::of::<T>();
+        self.custom_storages.remove(&type_key);
+        self.storage_types.insert(type_key, storage_type);
 
-        type_backing.insert(key, TypedData::new(value));
+        self.migrate_backing_for::<T>(StoreBacking::new::<T>(storage_type));
     }
 
-    pub fn remove<T>(&mut self, key: &Key)
+    /// Pin a specific StorageTrait implementation as the storage for type T, moving any existing keys and values into it
+    pub fn migrate_storage_for<T, S>(&mut self)
     where
         T: Debug + 'static,
+        S: StorageTrait<Key = Key, Value = T> + Default + 'static,
     {
-        if let Some(type_backing) = self.type_map.get(&TypeKey::of::<T>()) {
-            type_backing.remove(key);
-        }
+        self.assert_not_archetypal("Per-type storage selection");
+        let type_key = TypeKey::of::<T>();
+        self.storage_types.insert(type_key, StorageType::Custom);
+        self.custom_storages.insert(
+            type_key,
+            CustomStorage(Box::new(|| StoreBacking::with_storage(S::default()))),
+        );
+
+        self.migrate_backing_for::<T>(StoreBacking::with_storage(S::default()));
     }
 
-    pub fn remove_key(&mut self, key: &Key) {
-        for type_backing in self.type_map.values() {
-            type_backing.remove(key);
+    fn migrate_backing_for<T>(&mut self, new_backing: StoreBacking<Key>)
+    where
+        T: Debug + 'static,
+    {
+        let type_key = TypeKey::of::<T>();
+        if let Some(old_backing) = self.type_map.remove(&type_key) {
+            let new_backing = old_backing.migrate_into::<T>(new_backing);
+            self.type_map.insert(type_key, new_backing);
         }
     }
 
-    pub fn clear<T>(&mut self)
+    pub fn storage_type_for<T>(&self) -> Option<StorageType>
     where
         T: 'static,
     {
-        if let Some(type_backing) = self.type_map.get(&TypeKey::of::<T>()) {
-            type_backing.clear();
+        let type_backing = self.type_map.get(&TypeKey::of::<T>())?;
+        Some(type_backing.storage_type())
+    }
+
+    /// Start recording per-type access statistics, re-evaluating storage every `interval` inserts and removes.
+    /// Types are tracked from their next insert, and types pinned via register_storage_type_for or migrate_storage_type_for are left as-is.
+    pub fn enable_adaptive_storage(&mut self, interval: usize) {
+        self.assert_not_archetypal("Per-type storage selection");
+        self.adaptive_storage = Some(AdaptiveStorage::new(interval));
+    }
+
+    pub fn disable_adaptive_storage(&mut self) {
+        self.adaptive_storage = None;
+    }
+
+    pub fn adaptive_storage(&self) -> Option<&AdaptiveStorage> {
+        self.adaptive_storage.as_ref()
+    }
+
+    /// Move each tracked type to the storage best suited to its recorded accesses, then reset its statistics
+    pub fn adapt_storage(&mut self) {
+        let adaptive_storage = match &self.adaptive_storage {
+            Some(adaptive_storage) => adaptive_storage,
+            None => return,
+        };
+
+        let mut migrations: Vec<(TypeKey, StorageType)> = vec![];
+
+        for (type_key, statistics) in adaptive_storage.iter_statistics() {
+            // Too few accesses say nothing about how a type is used, so keep counting until there are enough
+            if self.storage_types.contains_key(type_key)
+                || statistics.accesses() < MIN_STORAGE_SAMPLES
+            {
+                continue;
+            }
+
+            if let Some(type_backing) = self.type_map.get(type_key) {
+                let storage_type = Self::storage_type_for_key(
+                    statistics
+                        .select_storage_type::<Key>(&type_backing.keys(), &self.storage_thresholds),
+                );
+
+                let current_storage_type = type_backing.storage_type();
+                if storage_type != current_storage_type
+                    && storage_type != StorageType::Unit
+                    && current_storage_type != StorageType::Unit
+                {
+                    migrations.push((*type_key, storage_type));
+                }
+            }
+
+            statistics.reset();
+        }
+
+        for (type_key, storage_type) in migrations {
+            let type_backing = self.type_map.remove(&type_key).unwrap();
+            self.type_map
+                .insert(type_key, type_backing.migrate(storage_type));
         }
     }
 
-    pub fn contains_type<T>(&self) -> bool
+    pub(crate) fn record_access<T>(&self, access: StorageAccess)
     where
         T: 'static,
     {
-        self.type_map.get(&TypeKey::of::<T>()).is_some()
+        if let Some(adaptive_storage) = &self.adaptive_storage {
+            adaptive_storage.record(&TypeKey::of::<T>(), access);
+        }
     }
 
-    pub fn contains_key(&self, key: &Key) -> bool {
-        self.type_map.values().any(|backing| backing.contains(key))
+    fn record_mutation(&mut self) {
+        if let Some(adaptive_storage) = &mut self.adaptive_storage {
+            if adaptive_storage.tick() {
+                self.adapt_storage();
+            }
+        }
     }
+}
 
-    pub fn contains_type_key<T>(&self, key: &Key) -> bool
+impl<Key> Store<Key>
+where
+    Key: StoreKey + 'static,
+{
+    pub fn get<T>(&self, key: &Key) -> Option<Ref<'_, T>>
     where
         T: 'static,
     {
-        if let Some(type_backing) = self.type_map.get(&TypeKey::of::<T>()) {
-            type_backing.contains(key)
-        } else {
-            false
+        if self.is_disabled(key) {
+            return None;
         }
+        self.get_including_disabled(key)
     }
 
-    pub fn keys<T>(&self) -> BitSet
+    pub fn get_mut<T>(&self, key: &Key) -> Option<RefMut<'_, T>>
     where
         T: 'static,
     {
-        if let Some(type_backing) = self.type_map.get(&TypeKey::of::<T>()) {
-            type_backing.keys()
-        } else {
-            BitSet::new()
+        if self.is_disabled(key) {
+            return None;
         }
+        self.get_mut_including_disabled(key)
+    }
+
+    /// As get, but also reaching the values of keys hidden by disable_key.
+    /// This is the opt-in for disabled keys, which StoreQuery::get rejects.
+    pub fn get_including_disabled<T>(&self, key: &Key) -> Option<Ref<'_, T>>
+    where
+        T: 'static,
+    {
+        self.record_access::<T>(StorageAccess::Random);
+        self.get_unrecorded(key)
     }
 
-    pub fn keys_all(&self) -> BitSet {
-        let mut bit_set = BitSet::new();
+    /// As get_mut, but also reaching the values of keys hidden by disable_key
+    pub fn get_mut_including_disabled<T>(&self, key: &Key) -> Option<RefMut<'_, T>>
+    where
+        T: 'static,
+    {
+        self.record_access::<T>(StorageAccess::Random);
+        self.get_mut_unrecorded(key)
+    }
 
-        for type_backing in self.type_map.values() {
-            bit_set |= &type_backing.keys();
+    /// Borrow the backing of type T once for repeated reads, or None if T has not been inserted.
+    /// Always None in archetype mode, where values of a type are split across tables.
+    pub fn storage<T>(&self) -> Option<StorageRef<'_, Key, T>>
+    where
+        T: 'static,
+    {
+        if self.archetypes.is_some() {
+            return None;
+        }
+        let type_backing = self.type_map.get(&TypeKey::of::<T>())?.downcast::<T>()?;
+        Some(StorageRef::new(
+            type_backing.keys.borrow(),
+            type_backing.values.borrow(),
+            type_backing.len(),
+            &self.key_allocator,
+        ))
+    }
+
+    /// Borrow the backing of type T once for repeated reads and writes, creating it if T has not been inserted.
+    /// Always None in archetype mode, where values of a type are split across tables.
+    pub fn storage_mut<T>(&mut self) -> Option<StorageMut<'_, Key, T>>
+    where
+        T: Debug + 'static,
+    {
+        if self.archetypes.is_some() {
+            return None;
+        }
+
+        let type_key = TypeKey::of::<T>();
+        if !self.type_map.contains_key(&type_key) {
+            let type_backing = self.create_storage_for::<T>();
+            self.type_map.insert(type_key, type_backing);
         }
 
-        bit_set
+        let type_backing = self.type_map[&type_key].downcast::<T>().unwrap();
+        Some(StorageMut::new(
+            type_backing.keys.borrow_mut(),
+            type_backing.values.borrow_mut(),
+            &type_backing.len,
+            &mut self.key_types,
+            &self.key_allocator,
+        ))
     }
 
-    pub fn iter_untyped(&self) -> impl Iterator<Item = (TypeKey, Ref<TypedData>)> {
-        let mut typed_data: Vec<(TypeKey, Ref<TypedData>)> = vec![];
+    /// Borrow the packed keys and values of type T, or None if T is absent or its storage is not dense.
+    /// Always None in archetype mode, where values of a type are split across tables.
+    pub fn column<T>(&self) -> Option<Column<'_, Key, T>>
+    where
+        T: 'static,
+    {
+        if self.archetypes.is_some() {
+            return None;
+        }
+        let type_backing = self.type_map.get(&TypeKey::of::<T>())?.downcast::<T>()?;
+        Column::new(type_backing.values.borrow())
+    }
 
-        for (type_key, store_backing) in &self.type_map {
-            for u32_key in store_backing.iter_keys() {
-                let key: &Key = &u32_key.into();
-                let data_ref = store_backing.get(key).unwrap();
-                typed_data.push((*type_key, data_ref));
+    /// Mutably borrow the packed values of type T, or None if T is absent or its storage is not dense.
+    /// Always None in archetype mode, where values of a type are split across tables.
+    pub fn column_mut<T>(&self) -> Option<ColumnMut<'_, Key, T>>
+    where
+        T: 'static,
+    {
+        if self.archetypes.is_some() {
+            return None;
+        }
+        let type_backing = self.type_map.get(&TypeKey::of::<T>())?.downcast::<T>()?;
+        ColumnMut::new(type_backing.values.borrow_mut())
+    }
+
+    /// Borrow the values of T for every row of a query segment, or None if they must be looked up by key or the segment lacks T
+    pub(crate) fn query_column<T>(&self, segment: QuerySegment) -> Option<QueryColumn<'_, T>>
+    where
+        T: 'static,
+    {
+        match segment {
+            QuerySegment::Dense(type_key) if type_key == TypeKey::of::<T>() => {
+                let type_backing = self.type_map.get(&type_key)?.downcast::<T>()?;
+                let values = Ref::filter_map(type_backing.values.borrow(), |values| {
+                    values.as_slices().map(|(_, values)| values)
+                });
+                values.ok().map(QueryColumn::new)
             }
+            QuerySegment::Table(table) => self
+                .archetypes
+                .as_ref()?
+                .column::<T>(table)
+                .map(QueryColumn::new),
+            _ => None,
         }
+    }
 
-        typed_data.into_iter()
+    pub(crate) fn query_column_mut<T>(&self, segment: QuerySegment) -> Option<QueryColumnMut<'_, T>>
+    where
+        T: 'static,
+    {
+        match segment {
+            QuerySegment::Dense(type_key) if type_key == TypeKey::of::<T>() => {
+                let type_backing = self.type_map.get(&type_key)?.downcast::<T>()?;
+                let values = RefMut::filter_map(type_backing.values.borrow_mut(), |values| {
+                    values.as_slices_mut().map(|(_, values)| values)
+                });
+                values.ok().map(QueryColumnMut::new)
+            }
+            QuerySegment::Table(table) => self
+                .archetypes
+                .as_ref()?
+                .column_mut::<T>(table)
+                .map(QueryColumnMut::new),
+            _ => None,
+        }
     }
 
-    pub fn iter_key_untyped(&self, key: &Key) -> impl Iterator<Item = (TypeKey, Ref<TypedData>)> {
-        let mut typed_data: Vec<(TypeKey, Ref<TypedData>)> = vec![];
+    pub(crate) fn get_iterated<T>(&self, key: &Key) -> Option<Ref<'_, T>>
+    where
+        T: 'static,
+    {
+        self.record_access::<T>(StorageAccess::Iterated);
+        self.get_unrecorded(key)
+    }
 
-        for (type_key, store_backing) in self.type_map.iter() {
-            if store_backing.contains(key) {
-                let data_ref = store_backing.get(key).unwrap();
-                typed_data.push((*type_key, data_ref));
-            }
+    pub(crate) fn get_mut_iterated<T>(&self, key: &Key) -> Option<RefMut<'_, T>>
+    where
+        T: 'static,
+    {
+        self.record_access::<T>(StorageAccess::Iterated);
+        self.get_mut_unrecorded(key)
+    }
+
+    fn get_unrecorded<T>(&self, key: &Key) -> Option<Ref<'_, T>>
+    where
+        T: 'static,
+    {
+        let key = &self.key_allocator.resolve(key)?;
+        if let Some(archetypes) = &self.archetypes {
+            return archetypes.get(key);
         }
 
-        typed_data.into_iter()
+        self.type_map.get(&TypeKey::of::<T>())?.get::<T>(key)
     }
 
-    pub fn iter_types(&self) -> impl Iterator<Item = &TypeKey> {
-        self.type_map.keys()
+    fn get_mut_unrecorded<T>(&self, key: &Key) -> Option<RefMut<'_, T>>
+    where
+        T: 'static,
+    {
+        let key = &self.key_allocator.resolve(key)?;
+        if let Some(archetypes) = &self.archetypes {
+            return archetypes.get_mut(key);
+        }
+
+        self.type_map.get(&TypeKey::of::<T>())?.get_mut::<T>(key)
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Insert a value for a key, panicking if the key is a stale handle
+    pub fn insert<T>(&mut self, key: Key, value: T)
+    where
+        T: Debug + 'static,
+    {
+        let key = self
+            .key_allocator
+            .resolve(&key)
+            .unwrap_or_else(|| panic!("Cannot insert through stale key {:?}", key));
+        assert_key_in_range(&key);
 
-    #[test]
-    fn iter_types() {
-        println!();
+        if let Some(archetypes) = &mut self.archetypes {
+            archetypes.insert(key, value);
+            return;
+        }
 
-        let mut store = Store::<u32>::default();
-        store.insert(0, "Hello");
-        store.insert(1, "Foo");
-        store.insert(2, "Bar");
+        let type_key = TypeKey::of::<T>();
+        if !self.type_map.contains_key(&type_key) {
+            let type_backing = self.create_storage_for::<T>();
+            self.type_map.insert(type_key, type_backing);
+        }
 
-        store.insert(0, 1);
-        store.insert(1, 2);
+        if self.type_map[&type_key].insert(key, value).is_none() {
+            self.key_types.add(key, type_key);
+        }
 
-        store.insert(0, 0..10);
+        if let Some(adaptive_storage) = &mut self.adaptive_storage {
+            adaptive_storage.register(TypeKey::of::<T>(), std::mem::size_of::<T>());
+            adaptive_storage.record(&TypeKey::of::<T>(), StorageAccess::Insert);
+            self.record_mutation();
+        }
+    }
 
-        for key in store.iter_types() {
-            println!("Type: {:?}", key);
+    pub fn remove<T>(&mut self, key: &Key)
+    where
+        T: Debug + 'static,
+    {
+        let key = match self.key_allocator.resolve(key) {
+            Some(key) => key,
+            None => return,
+        };
+        let key = &key;
+
+        if let Some(archetypes) = &mut self.archetypes {
+            archetypes.remove(key, &TypeKey::of::<T>());
+            return;
+        }
+
+        if let Some(type_backing) = self.type_map.get(&TypeKey::of::<T>()) {
+            if type_backing.remove_untyped(key) {
+                self.key_types.remove(key, &TypeKey::of::<T>());
+            }
+            self.record_access::<T>(StorageAccess::Remove);
+            self.record_mutation();
         }
     }
 
-    #[test]
-    fn iter_untyped() {
-        println!();
+    /// Reserve a key that holds no values, reusing keys freed by remove_key before allocating new ones.
+    /// Takes a shared reference so keys can be reserved while the store is borrowed, e.g. during iteration.
+    pub fn create_key(&self) -> Key {
+        let index = self.key_allocator.allocate(|index| {
+            let key = Key::from_index(index);
+            self.holds_key(&self.key_at(index))
+                || self.key_aliases.contains_key(&key)
+                || self.disabled.contains(&key)
+        });
+        self.key_at(index)
+    }
 
-        let mut store = Store::<u32>::default();
-        store.insert(0, "Hello");
-        store.insert(1, "Foo");
-        store.insert(2, "Bar");
+    /// The current handle for a key index, e.g. that of a key in the set returned by keys or keys_all
+    pub fn key_at(&self, index: u64) -> Key {
+        self.key_allocator.current(index)
+    }
 
-        store.insert(0, 1);
-        store.insert(1, 2);
+    /// Whether a handle still refers to its key, i.e. the key has not been removed via remove_key since it was issued
+    pub fn is_current(&self, key: &Key) -> bool {
+        self.key_allocator.resolve(key).is_some()
+    }
 
-        store.insert(0, 0..10);
+    /// Reserve a key with create_key and assemble the provided fields onto it
+    pub fn spawn<T>(&mut self, tuple: T) -> Key
+    where
+        Key: Assemble<T>,
+    {
+        self.create_key().assemble(self, tuple)
+    }
 
-        for (key, value) in store.iter_untyped() {
-            println!("Type: {:?}, Data: {:?}", key, value);
-        }
+    /// Reserve a key with create_key, returning a guard that calls remove_key when dropped
+    pub fn scoped_key(&mut self) -> ScopedKey<'_, Key> {
+        let key = self.create_key();
+        ScopedKey::new(self, key)
     }
 
-    #[test]
-    fn iter_key_untyped() {
-        println!();
+    /// Name a key so it can be found via lookup, returning the key the name previously referred to.
+    /// A key may hold several names, all of which are dropped when the key is removed via remove_key.
+    pub fn alias<N>(&mut self, key: Key, name: N) -> Option<Key>
+    where
+        N: Into<String>,
+    {
+        let key = self
+            .key_allocator
+            .resolve(&key)
+            .unwrap_or_else(|| panic!("Cannot alias stale key {:?}", key));
 
-        let mut store = Store::<u32>::default();
-        store.insert(0, "Hello");
-        store.insert(1, "Foo");
-        store.insert(2, "Bar");
+        let previous = self.key_aliases.alias(key, name.into())?;
+        Some(self.key_at(previous.to_index()))
+    }
 
-        store.insert(0, 1);
-        store.insert(1, 2);
+    /// Remove a name, returning the key it referred to
+    pub fn unalias(&mut self, name: &str) -> Option<Key> {
+        let key = self.key_aliases.unalias(name)?;
+        Some(self.key_at(key.to_index()))
+    }
 
-        store.insert(0, 0..10);
+    /// The key a name refers to
+    pub fn lookup(&self, name: &str) -> Option<Key> {
+        let key = self.key_aliases.lookup(name)?;
+        Some(self.key_at(key.to_index()))
+    }
 
-        for i in 0..3 {
-            println!("Key {}:", i);
-            for (key, value) in store.iter_key_untyped(&i) {
-                println!("Type: {:?}, Data: {:?}", key, value);
-            }
+    /// Names of a key, in the order they were registered
+    pub fn aliases_of(&self, key: &Key) -> &[String] {
+        match self.key_allocator.resolve(key) {
+            Some(key) => self.key_aliases.aliases_of(&key),
+            None => &[],
+        }
+    }
+
+    /// Hide a key from queries, keys, keys_all, iter_untyped, contains_key and get without removing its values, until enable_key is called.
+    /// Its values stay reachable via get_including_disabled, types_of, iter_key_untyped and storage views.
+    pub fn disable_key(&mut self, key: &Key) {
+        if let Some(key) = self.key_allocator.resolve(key) {
+            self.disabled.insert(&key);
+        }
+    }
+
+    pub fn enable_key(&mut self, key: &Key) {
+        if let Some(key) = self.key_allocator.resolve(key) {
+            self.disabled.remove(&key);
         }
     }
+
+    pub fn is_disabled(&self, key: &Key) -> bool {
+        self.key_allocator
+            .resolve(key)
+            .is_some_and(|key| self.disabled.contains(&key))
+    }
+
+    /// Remove every value and name held by a key, invalidating existing handles to it and freeing it for reuse by create_key
+    pub fn remove_key(&mut self, key: &Key) {
+        if !self.holds_key(key) && self.aliases_of(key).is_empty() && !self.is_disabled(key) {
+            // Keys handed out by create_key are released even if nothing was inserted under them
+            let allocated = self
+                .key_allocator
+                .resolve(key)
+                .is_some_and(|key| self.key_allocator.is_allocated(key.to_index()));
+            if !allocated {
+                return;
+            }
+        }
+
+        let key = &match self.key_allocator.resolve(key) {
+            Some(key) => key,
+            None => return,
+        };
+        self.key_allocator.release(key.to_index());
+        self.key_aliases.remove_key(key);
+        self.disabled.remove(key);
+
+        if let Some(archetypes) = &mut self.archetypes {
+            archetypes.remove_key(key);
+            return;
+        }
+
+        for type_key in self.key_types.remove_key(key) {
+            self.type_map[&type_key].remove_untyped(key);
+            if let Some(adaptive_storage) = &self.adaptive_storage {
+                adaptive_storage.record(&type_key, StorageAccess::Remove);
+            }
+        }
+
+        self.record_mutation();
+    }
+
+    pub fn clear<T>(&mut self)
+    where
+        T: 'static,
+    {
+        if let Some(archetypes) = &mut self.archetypes {
+            let type_key = TypeKey::of::<T>();
+            for key in archetypes.keys(&type_key).into_keys() {
+                archetypes.remove(&key, &type_key);
+            }
+            return;
+        }
+
+        let type_key = TypeKey::of::<T>();
+        if let Some(type_backing) = self.type_map.get(&type_key) {
+            for key in type_backing.keys().into_keys() {
+                self.key_types.remove(&key, &type_key);
+            }
+            self.type_map[&type_key].clear();
+        }
+    }
+
+    pub fn contains_type<T>(&self) -> bool
+    where
+        T: 'static,
+    {
+        if let Some(archetypes) = &self.archetypes {
+            return archetypes.contains_type(&TypeKey::of::<T>());
+        }
+
+        self.type_map.contains_key(&TypeKey::of::<T>())
+    }
+
+    /// Whether a key holds any values and has not been disabled
+    pub fn contains_key(&self, key: &Key) -> bool {
+        self.holds_key(key) && !self.is_disabled(key)
+    }
+
+    /// Whether a key holds any values, disabled or not
+    fn holds_key(&self, key: &Key) -> bool {
+        let key = match self.key_allocator.resolve(key) {
+            Some(key) => key,
+            None => return false,
+        };
+        let key = &key;
+
+        if let Some(archetypes) = &self.archetypes {
+            return archetypes.contains_key(key);
+        }
+
+        self.key_types.contains_key(key)
+    }
+
+    /// The types currently held by a key, in TypeKey order
+    pub fn types_of(&self, key: &Key) -> &[TypeKey] {
+        let key = match self.key_allocator.resolve(key) {
+            Some(key) => key,
+            None => return &[],
+        };
+        let key = &key;
+
+        if let Some(archetypes) = &self.archetypes {
+            return archetypes.types_of(key);
+        }
+
+        self.key_types.types_of(key)
+    }
+
+    pub fn contains_type_key<T>(&self, key: &Key) -> bool
+    where
+        T: 'static,
+    {
+        let key = match self.key_allocator.resolve(key) {
+            Some(key) => key,
+            None => return false,
+        };
+        let key = &key;
+
+        if let Some(archetypes) = &self.archetypes {
+            return archetypes.contains_type_key(key, &TypeKey::of::<T>());
+        }
+
+        if let Some(type_backing) = self.type_map.get(&TypeKey::of::<T>()) {
+            type_backing.contains(key)
+        } else {
+            false
+        }
+    }
+
+    pub fn keys<T>(&self) -> Keys<Key>
+    where
+        T: 'static,
+    {
+        let mut keys = if let Some(archetypes) = &self.archetypes {
+            archetypes.keys(&TypeKey::of::<T>())
+        } else if let Some(type_backing) = self.type_map.get(&TypeKey::of::<T>()) {
+            type_backing.keys()
+        } else {
+            KeySetOf::<Key>::default()
+        };
+
+        keys.difference_with(&self.disabled);
+        Keys::new(keys)
+    }
+
+    /// Keys of type T in packed order, if its storage keeps them contiguous
+    pub(crate) fn dense_keys<T>(&self) -> Option<Vec<Key>>
+    where
+        T: 'static,
+    {
+        self.type_map.get(&TypeKey::of::<T>())?.dense_keys()
+    }
+
+    /// Keys holding every `required` type and no `excluded` type, in the order a query should visit them.
+    /// Archetype mode walks only the matching tables; otherwise key sets are intersected and a dense required storage, if any, sets the order.
+    pub(crate) fn query_keys(
+        &self,
+        required: &[TypeKey],
+        excluded: &[TypeKey],
+    ) -> StoreIteratorKeys<Key> {
+        if let Some(archetypes) = &self.archetypes {
+            let mut rows = archetypes.query_rows(required, excluded);
+            rows.retain(|(key, _, _)| !self.disabled.contains(key));
+            return StoreIteratorKeys::from_rows(rows);
+        }
+
+        let keys = self.query_key_set(required, excluded);
+
+        // Drive iteration from the first required type with dense storage, if any
+        let dense_keys = required.iter().find_map(|type_key| {
+            let dense_keys = self.type_map.get(type_key)?.dense_keys()?;
+            Some((*type_key, dense_keys))
+        });
+
+        StoreIteratorKeys::new(keys, dense_keys)
+    }
+
+    /// Keys holding every `required` type and no `excluded` type that fall within `range`, in ascending order.
+    /// The first required type's keys are taken over the range, and only those are probed against the other types.
+    pub(crate) fn query_range_keys(
+        &self,
+        required: &[TypeKey],
+        excluded: &[TypeKey],
+        range: &KeyRange<Key>,
+    ) -> StoreIteratorKeys<Key> {
+        if let Some(archetypes) = &self.archetypes {
+            let mut keys = archetypes.query_keys(required, excluded);
+            keys.sort();
+            keys.retain(|key| range.contains(key) && !self.disabled.contains(key));
+            return StoreIteratorKeys::from_keys(keys);
+        }
+
+        let (driving, others) = match required.split_first() {
+            Some(split) => split,
+            None => {
+                return StoreIteratorKeys::from_keys(
+                    self.query_key_set(required, excluded).range(range),
+                )
+            }
+        };
+        let range_keys = match self.type_map.get(driving) {
+            Some(type_backing) => type_backing.range_keys(range),
+            None => vec![],
+        };
+
+        let holds = |type_key: &TypeKey, key: &Key| {
+            self.type_map
+                .get(type_key)
+                .is_some_and(|type_backing| type_backing.contains(key))
+        };
+
+        StoreIteratorKeys::from_keys(
+            range_keys
+                .into_iter()
+                .filter(|key| {
+                    !self.disabled.contains(key)
+                        && others.iter().all(|type_key| holds(type_key, key))
+                        && !excluded.iter().any(|type_key| holds(type_key, key))
+                })
+                .collect(),
+        )
+    }
+
+    fn query_key_set(&self, required: &[TypeKey], excluded: &[TypeKey]) -> KeySetOf<Key> {
+        let type_keys = |type_key: &TypeKey| {
+            self.type_map
+                .get(type_key)
+                .map_or_else(KeySetOf::<Key>::default, |type_backing| type_backing.keys())
+        };
+
+        let mut keys = self.keys_all().into_key_set();
+        for type_key in excluded {
+            keys.difference_with(&type_keys(type_key));
+        }
+        for type_key in required {
+            keys.intersect_with(&type_keys(type_key));
+        }
+
+        keys
+    }
+
+    /// Keys holding any values, excluding disabled keys
+    pub fn keys_all(&self) -> Keys<Key> {
+        let mut keys = self.keys_all_including_disabled();
+        keys.difference_with(&self.disabled);
+        Keys::new(keys)
+    }
+
+    fn keys_all_including_disabled(&self) -> KeySetOf<Key> {
+        if let Some(archetypes) = &self.archetypes {
+            return archetypes.keys_all();
+        }
+
+        let mut key_set = KeySetOf::<Key>::default();
+
+        for type_backing in self.type_map.values() {
+            key_set.union_with(&type_backing.keys());
+        }
+
+        key_set
+    }
+
+    /// Iterate over the values of type T in the order its storage packs them.
+    /// For InsertionOrderedMap this is insertion order, stable across removals; unordered storages yield ascending keys.
+    pub fn iter_ordered<T>(&self) -> impl Iterator<Item = (Key, Ref<'_, T>)>
+    where
+        T: 'static,
+    {
+        let keys = self
+            .dense_keys::<T>()
+            .unwrap_or_else(|| self.keys::<T>().into_iter().collect());
+
+        keys.into_iter()
+            .filter(move |key| !self.disabled.contains(key))
+            .map(move |key| {
+                let key = self.key_at(key.to_index());
+                (key, self.get_iterated::<T>(&key).unwrap())
+            })
+    }
+
+    /// Iterate over the values of type T whose keys fall within `range`, in ascending key order
+    pub fn range<T, R>(&self, range: R) -> impl Iterator<Item = (Key, Ref<'_, T>)>
+    where
+        T: 'static,
+        R: RangeBounds<Key>,
+    {
+        let keys = self.range_keys::<T>(&key_range(&range));
+
+        keys.into_iter()
+            .filter(move |key| !self.disabled.contains(key))
+            .map(move |key| {
+                let key = self.key_at(key.to_index());
+                (key, self.get_iterated::<T>(&key).unwrap())
+            })
+    }
+
+    fn range_keys<T>(&self, range: &KeyRange<Key>) -> Vec<Key>
+    where
+        T: 'static,
+    {
+        if self.archetypes.is_some() {
+            return self.keys::<T>().into_key_set().range(range);
+        }
+
+        match self.type_map.get(&TypeKey::of::<T>()) {
+            Some(type_backing) => type_backing.range_keys(range),
+            None => vec![],
+        }
+    }
+
+    pub fn iter_untyped(&self) -> impl Iterator<Item = (TypeKey, Ref<'_, dyn TypedData>)> {
+        if let Some(archetypes) = &self.archetypes {
+            return archetypes.iter_untyped(&self.disabled).into_iter();
+        }
+
+        let mut typed_data: Vec<(TypeKey, Ref<dyn TypedData>)> = vec![];
+
+        for (type_key, store_backing) in &self.type_map {
+            for key in store_backing
+                .iter_keys()
+                .filter(|key| !self.disabled.contains(key))
+            {
+                let data_ref = store_backing.get_untyped(&key).unwrap();
+                typed_data.push((*type_key, data_ref));
+            }
+        }
+
+        typed_data.into_iter()
+    }
+
+    pub fn iter_key_untyped(
+        &self,
+        key: &Key,
+    ) -> impl Iterator<Item = (TypeKey, Ref<'_, dyn TypedData>)> {
+        let key = match self.key_allocator.resolve(key) {
+            Some(key) => key,
+            None => return vec![].into_iter(),
+        };
+        let key = &key;
+
+        if let Some(archetypes) = &self.archetypes {
+            return archetypes.iter_key_untyped(key).into_iter();
+        }
+
+        let mut typed_data: Vec<(TypeKey, Ref<dyn TypedData>)> = vec![];
+
+        for type_key in self.types_of(key) {
+            let data_ref = self.type_map[type_key].get_untyped(key).unwrap();
+            typed_data.push((*type_key, data_ref));
+        }
+
+        typed_data.into_iter()
+    }
+
+    pub fn iter_types(&self) -> impl Iterator<Item = &TypeKey> {
+        let types: Vec<&TypeKey> = match &self.archetypes {
+            Some(archetypes) => archetypes.types(),
+            None => self.type_map.keys().collect(),
+        };
+        types.into_iter()
+    }
+}
+
+/// Keys holding values or names, including disabled keys, each labelled by its first name if it has one
+impl<Key> Debug for Store<Key>
+where
+    Key: StoreKey + 'static,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut keys = self.keys_all_including_disabled();
+        for key in self.key_aliases.keys() {
+            keys.insert(&key);
+        }
+
+        f.write_str("Store ")?;
+        f.debug_map()
+            .entries(keys.into_keys().map(|key| {
+                let key = self.key_at(key.to_index());
+                let values = self.iter_key_untyped(&key).collect::<Vec<_>>();
+                (
+                    DebugKey(key, self.aliases_of(&key).first()),
+                    DebugValues(values),
+                )
+            }))
+            .finish()
+    }
+}
+
+struct DebugKey<'a, Key>(Key, Option<&'a String>);
+
+impl<'a, Key> Debug for DebugKey<'a, Key>
+where
+    Key: Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.1 {
+            Some(name) => Debug::fmt(name, f),
+            None => Debug::fmt(&self.0, f),
+        }
+    }
+}
+
+struct DebugValues<'a>(Vec<(TypeKey, Ref<'a, dyn TypedData>)>);
+
+impl<'a> Debug for DebugValues<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map()
+            .entries(self.0.iter().map(|(type_key, value)| (type_key, value)))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn iter_types() {
+        println!();
+
+        let mut store = Store::<u32>::default();
+        store.insert(0, "Hello");
+        store.insert(1, "Foo");
+        store.insert(2, "Bar");
+
+        store.insert(0, 1);
+        store.insert(1, 2);
+
+        store.insert(0, 0..10);
+
+        for key in store.iter_types() {
+            println!("Type: {:?}", key);
+        }
+    }
+
+    #[test]
+    fn iter_untyped() {
+        println!();
+
+        let mut store = Store::<u32>::default();
+        store.insert(0, "Hello");
+        store.insert(1, "Foo");
+        store.insert(2, "Bar");
+
+        store.insert(0, 1);
+        store.insert(1, 2);
+
+        store.insert(0, 0..10);
+
+        for (key, value) in store.iter_untyped() {
+            println!("Type: {:?}, Data: {:?}", key, value);
+        }
+    }
+
+    #[test]
+    fn iter_key_untyped() {
+        println!();
+
+        let mut store = Store::<u32>::default();
+        store.insert(0, "Hello");
+        store.insert(1, "Foo");
+        store.insert(2, "Bar");
+
+        store.insert(0, 1);
+        store.insert(1, 2);
+
+        store.insert(0, 0..10);
+
+        for i in 0..3 {
+            println!("Key {}:", i);
+            for (key, value) in store.iter_key_untyped(&i) {
+                println!("Type: {:?}, Data: {:?}", key, value);
+            }
+        }
+    }
+
+    #[test]
+    fn types_of() {
+        for mut store in [Store::<u32>::default(), Store::<u32>::with_archetypes()] {
+            store.insert(0, "Hello");
+            store.insert(0, 1);
+            store.insert(0, 'a');
+            store.insert(1, 'b');
+            store.insert(0, 2);
+
+            let mut expected = vec![
+                TypeKey::of::<&str>(),
+                TypeKey::of::<i32>(),
+                TypeKey::of::<char>(),
+            ];
+            expected.sort();
+            assert_eq!(store.types_of(&0), expected.as_slice());
+            assert_eq!(store.types_of(&1), &[TypeKey::of::<char>()]);
+            assert_eq!(store.types_of(&2), &[]);
+            assert_eq!(store.iter_key_untyped(&0).count(), 3);
+
+            store.remove::<i32>(&0);
+            store.remove::<i32>(&1);
+            assert_eq!(store.types_of(&0).len(), 2);
+
+            store.clear::<char>();
+            assert_eq!(store.types_of(&0), &[TypeKey::of::<&str>()]);
+            assert!(!store.contains_key(&1));
+
+            store.remove_key(&0);
+            assert!(!store.contains_key(&0));
+            assert_eq!(store.types_of(&0), &[]);
+            assert!(store.get::<&str>(&0).is_none());
+        }
+    }
+
+    #[test]
+    fn default_vec_map_membership() {
+        let mut store = Store::<u32>::default();
+        store.register_storage_for::<i32, DefaultVecMap<u32, i32>>();
+
+        store.insert(4, 4);
+        store.insert(1, 1);
+
+        assert!(store.get::<i32>(&0).is_none());
+        assert!(!store.contains_type_key::<i32>(&2));
+        assert_eq!(*store.get::<i32>(&4).unwrap(), 4);
+
+        store.remove::<i32>(&4);
+        assert!(store.get::<i32>(&4).is_none());
+        assert_eq!(store.keys::<i32>().into_iter().collect::<Vec<_>>(), vec![1]);
+    }
+
+    #[test]
+    fn register_storage_type_for() {
+        let mut store = Store::<u32>::default();
+        store.register_storage_type_for::<String>(StorageType::BTreeMap);
+
+        store.insert(0, "Hello".to_string());
+        store.insert(1, 1u8);
+
+        assert_eq!(
+            store.storage_type_for::<String>(),
+            Some(StorageType::BTreeMap)
+        );
+        assert_eq!(
+            store.storage_type_for::<u8>(),
+            Some(StorageType::SparseVecMap)
+        );
+        assert_eq!(store.storage_type_for::<i64>(), None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn register_storage_type_for_after_insert() {
+        let mut store = Store::<u32>::default();
+        store.insert(0, "Hello".to_string());
+        store.register_storage_type_for::<String>(StorageType::BTreeMap);
+    }
+
+    #[test]
+    fn migrate_storage_type_for() {
+        #[derive(Debug, Copy, Clone, Eq, PartialEq)]
+        struct Marker;
+
+        let mut store = Store::<u32>::default();
+        store.insert(0, 'a');
+        store.insert(2, 'b');
+        store.insert(5, 'c');
+        store.insert(1, Marker);
+        store.insert(3, Marker);
+
+        for storage_type in [
+            StorageType::BTreeMap,
+            StorageType::HashMap,
+            StorageType::DenseVecMap,
+            StorageType::PagedSparseVecMap,
+            StorageType::InsertionOrderedMap,
+            StorageType::SparseVecMap,
+        ]
+        .iter()
+        {
+            store.migrate_storage_type_for::<char>(*storage_type);
+            store.migrate_storage_type_for::<Marker>(*storage_type);
+
+            assert_eq!(store.storage_type_for::<char>(), Some(*storage_type));
+            assert_eq!(
+                store.keys::<char>().into_iter().collect::<Vec<_>>(),
+                vec![0, 2, 5]
+            );
+            assert_eq!(*store.get::<char>(&2).unwrap(), 'b');
+            assert_eq!(*store.get::<char>(&5).unwrap(), 'c');
+
+            assert_eq!(
+                store.keys::<Marker>().into_iter().collect::<Vec<_>>(),
+                vec![1, 3]
+            );
+            assert_eq!(*store.get::<Marker>(&3).unwrap(), Marker);
+
+            store.migrate_storage_type_for::<Marker>(StorageType::Unit);
+        }
+
+        store.migrate_storage_for::<char, DefaultVecMap<u32, char>>();
+        assert_eq!(
+            store.storage_type_for::<char>(),
+            Some(StorageType::DefaultVecMap)
+        );
+        assert_eq!(
+            store.keys::<char>().into_iter().collect::<Vec<_>>(),
+            vec![0, 2, 5]
+        );
+        assert!(store.get::<char>(&1).is_none());
+        assert_eq!(*store.get::<char>(&5).unwrap(), 'c');
+
+        println!("\nStore: {:#?}\n", store);
+    }
+
+    #[test]
+    fn range() {
+        let mut archetypal_store = Store::<u32>::with_archetypes();
+        let mut default_store = Store::<u32>::default();
+        default_store.register_storage_type_for::<&str>(StorageType::BTreeMap);
+
+        for store in [&mut default_store, &mut archetypal_store] {
+            for (key, word) in [
+                (9, "nine"),
+                (2, "two"),
+                (6, "six"),
+                (4, "four"),
+                (11, "eleven"),
+            ] {
+                store.insert(key, word);
+                store.insert(key, key as u64);
+            }
+
+            let words: Vec<(u32, &str)> = store
+                .range::<&str, _>(4..10)
+                .map(|(key, word)| (key, *word))
+                .collect();
+            assert_eq!(words, vec![(4, "four"), (6, "six"), (9, "nine")]);
+
+            let keys: Vec<u32> = store.range::<u64, _>(..=6).map(|(key, _)| key).collect();
+            assert_eq!(keys, vec![2, 4, 6]);
+
+            assert_eq!(store.range::<u64, _>(12..).count(), 0);
+            assert_eq!(store.range::<char, _>(..).count(), 0);
+
+            // Inverted and empty ranges select nothing rather than panicking
+            let (start, end) = (9, 4);
+            assert_eq!(store.range::<&str, _>(start..end).count(), 0);
+            assert_eq!(
+                store
+                    .range::<&str, _>((std::ops::Bound::Excluded(6), std::ops::Bound::Excluded(6)))
+                    .count(),
+                0
+            );
+            assert_eq!(
+                StoreQuery::<(u32, Ref<&str>)>::iter_range(&*store, start..=end).count(),
+                0
+            );
+        }
+    }
+
+    #[test]
+    fn create_key() {
+        let mut store = Store::<u32>::default();
+        store.insert(1, "manual");
+
+        let first = store.create_key();
+        let second = store.create_key();
+        let third = store.create_key();
+        assert_eq!((first, second, third), (0, 2, 3));
+
+        store.insert(first, "first");
+        store.insert(second, "second");
+        store.insert(third, "third");
+
+        // Keys can be reserved while the store is being iterated
+        let reserved: Vec<u32> = store
+            .iter_ordered::<&str>()
+            .map(|_| store.create_key())
+            .collect();
+        assert_eq!(reserved, vec![4, 5, 6, 7]);
+
+        store.remove_key(&second);
+        store.remove_key(&second);
+        store.remove_key(&100);
+        assert_eq!(store.create_key(), second);
+        assert_eq!(store.create_key(), 8);
+
+        let spawned = store.spawn(("spawned", 1.5f32));
+        assert_eq!(spawned, 9);
+        assert_eq!(*store.get::<&str>(&spawned).unwrap(), "spawned");
+
+        StoreBuilder::new()
+            .spawn_field(&store, 'a')
+            .map_spawn(&store, |key_builder| key_builder.field('b').field(2u8))
+            .spawn_fields(&store, ('c', 3u8))
+            .finish(&mut store);
+
+        let chars: Vec<(u32, char)> = store
+            .iter_ordered::<char>()
+            .map(|(key, value)| (key, *value))
+            .collect();
+        assert_eq!(chars, vec![(10, 'a'), (11, 'b'), (12, 'c')]);
+    }
+
+    #[test]
+    fn release_reinserted_key() {
+        let mut store = Store::<u32>::default();
+        let key = store.spawn(("first",));
+        store.remove_key(&key);
+        store.insert(key, "second");
+        store.remove_key(&key);
+
+        let first = store.create_key();
+        let second = store.create_key();
+        println!("{} {}", first, second);
+        assert_eq!(first, key);
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn remove_empty_created_key() {
+        let mut store = Store::<u32>::default();
+        let first = store.create_key();
+        store.remove_key(&first);
+        assert_eq!(store.create_key(), first);
+
+        let mut store = Store::<GenerationalKey>::default();
+        let empty = store.create_key();
+        store.remove_key(&empty);
+        println!("{:?}", empty);
+        assert!(!store.is_current(&empty));
+
+        let reused = store.create_key();
+        assert_eq!(reused.index(), empty.index());
+        assert_ne!(reused, empty);
+
+        // Keys never handed out are left alone
+        let untouched = GenerationalKey::from_index(5);
+        store.remove_key(&untouched);
+        assert!(store.is_current(&untouched));
+    }
+
+    #[test]
+    fn wide_keys() {
+        let mut store = Store::<u64>::default();
+        let far = 1 << 40;
+
+        store.insert(far, "far");
+        store.insert(far + 1, "farther");
+        store.insert(3, "near");
+        store.insert(far, 1.5f32);
+        assert_eq!(store.storage_type_for::<&str>(), Some(StorageType::HashMap));
+
+        let strings: Vec<(u64, &str)> = StoreQuery::<(u64, Ref<&str>)>::iter(&store)
+            .map(|(key, string)| (key, *string))
+            .collect();
+        println!("{:?}", strings);
+        assert_eq!(
+            strings,
+            vec![(3, "near"), (far, "far"), (far + 1, "farther")]
+        );
+
+        let keys: Vec<u64> = StoreQuery::<(u64, Ref<&str>, Ref<f32>)>::iter(&store)
+            .map(|(key, _, _)| key)
+            .collect();
+        assert_eq!(keys, vec![far]);
+
+        let keys: Vec<u64> = store.range::<&str, _>(4..).map(|(key, _)| key).collect();
+        assert_eq!(keys, vec![far, far + 1]);
+        assert_eq!(store.keys_all().len(), 3);
+
+        store.remove_key(&far);
+        assert!(!store.contains_key(&far));
+        assert_eq!(store.range::<&str, _>(4..).count(), 1);
+
+        let created: Vec<u64> = (0..4).map(|_| store.create_key()).collect();
+        assert_eq!(created, vec![0, 1, 2, 4]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn wide_keys_index_addressed() {
+        let mut store = Store::<u64>::default();
+        store.register_storage_type_for::<i32>(StorageType::SparseVecMap);
+    }
+
+    #[test]
+    fn disabled_keys() {
+        for mut store in [Store::<u32>::default(), Store::<u32>::with_archetypes()] {
+            let a = store.spawn(("a", 1i32));
+            let b = store.spawn(("b", 2i32));
+            let c = store.spawn(("c",));
+
+            store.disable_key(&b);
+            assert!(store.is_disabled(&b));
+            assert!(!store.contains_key(&b));
+            assert!(store.get::<&str>(&b).is_none());
+            assert_eq!(*store.get_including_disabled::<&str>(&b).unwrap(), "b");
+            *store.get_mut_including_disabled::<i32>(&b).unwrap() += 10;
+
+            let mut keys: Vec<u32> = StoreQuery::<(u32, Ref<&str>)>::iter(&store)
+                .map(|(key, _)| key)
+                .collect();
+            keys.sort();
+            println!("{:?}", keys);
+            assert_eq!(keys, vec![a, c]);
+            assert_eq!(
+                StoreQuery::<(u32, Ref<&str>, Ref<i32>)>::iter_keys(&store, &[a, b]).count(),
+                1
+            );
+            assert_eq!(store.range::<&str, _>(..).count(), 2);
+            assert_eq!(store.iter_ordered::<i32>().count(), 1);
+            assert_eq!(store.keys_all().into_iter().collect::<Vec<_>>(), vec![a, c]);
+            assert_eq!(store.iter_untyped().count(), 3);
+
+            let created = store.create_key();
+            assert_ne!(created, b);
+
+            store.enable_key(&b);
+            assert_eq!(*store.get::<i32>(&b).unwrap(), 12);
+            assert_eq!(StoreQuery::<(u32, Ref<&str>)>::iter(&store).count(), 3);
+
+            store.disable_key(&b);
+            store.remove_key(&b);
+            assert!(store.types_of(&b).is_empty());
+            assert!(!store.is_disabled(&b));
+            assert_eq!(StoreQuery::<(u32, Ref<&str>)>::iter(&store).count(), 2);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "is disabled")]
+    fn disabled_key_query_get() {
+        let mut store = Store::<u32>::default();
+        let key = store.spawn(("hidden",));
+        store.disable_key(&key);
+        StoreQuery::<(u32, Ref<&str>)>::get(&store, &key);
+    }
+
+    #[test]
+    fn aliases() {
+        let mut store = Store::<GenerationalKey>::default();
+        let player = store.spawn(("Player", 10i32));
+        let enemy = store.spawn(("Enemy",));
+        let camera = store.create_key();
+
+        assert_eq!(store.alias(player, "player"), None);
+        store.alias(player, "5f0c-uuid");
+        store.alias(enemy, "target");
+        store.alias(camera, "camera");
+        assert_eq!(store.lookup("player"), Some(player));
+        assert_eq!(store.lookup("5f0c-uuid"), Some(player));
+        assert_eq!(store.aliases_of(&player), ["player", "5f0c-uuid"]);
+
+        // Aliased keys are in use even without values
+        let other = store.create_key();
+        assert_ne!(other, camera);
+
+        let debug = format!("{:?}", store);
+        println!("{}", debug);
+        assert!(debug.starts_with(r#"Store {"player": {"#));
+        assert!(debug.contains(r#"i32: 10"#));
+        assert!(debug.ends_with(r#""target": {&str: "Enemy"}, "camera": {}}"#));
+
+        assert_eq!(store.alias(enemy, "player"), Some(player));
+        assert_eq!(store.lookup("player"), Some(enemy));
+        assert_eq!(store.aliases_of(&player), ["5f0c-uuid"]);
+        assert_eq!(store.unalias("target"), Some(enemy));
+        assert_eq!(store.lookup("target"), None);
+
+        store.remove_key(&enemy);
+        assert_eq!(store.lookup("player"), None);
+        assert!(store.aliases_of(&enemy).is_empty());
+
+        store.remove_key(&camera);
+        assert_eq!(store.lookup("camera"), None);
+        assert!(!store.is_current(&camera));
+    }
+
+    #[test]
+    fn iter_ordered() {
+        let mut store = Store::<u32>::default();
+        store.register_storage_type_for::<&str>(StorageType::InsertionOrderedMap);
+
+        store.insert(7, "first");
+        store.insert(2, "second");
+        store.insert(9, "third");
+        store.insert(4, "fourth");
+        store.remove::<&str>(&2);
+        store.insert(7, "first, edited");
+        store.insert(1, "fifth");
+
+        store.insert(9, 9);
+        store.insert(1, 1);
+
+        let lines: Vec<(u32, &str)> = store
+            .iter_ordered::<&str>()
+            .map(|(key, line)| (key, *line))
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                (7, "first, edited"),
+                (9, "third"),
+                (4, "fourth"),
+                (1, "fifth")
+            ]
+        );
+
+        let keys: Vec<u32> = store.iter_ordered::<i32>().map(|(key, _)| key).collect();
+        assert_eq!(keys, vec![1, 9]);
+
+        let keys: Vec<u32> = StoreQuery::<(u32, Ref<&str>, Ref<i32>)>::iter(&store)
+            .map(|(key, _, _)| key)
+            .collect();
+        assert_eq!(keys, vec![9, 1]);
+    }
+
+    #[test]
+    fn archetypes() {
+        let value = std::rc::Rc::new(());
+
+        let mut store = Store::<u32>::with_archetypes();
+        store.insert(0, 'a');
+        store.insert(1, 'b');
+        store.insert(2, 'c');
+        store.insert(1, value.clone());
+        store.insert(2, value.clone());
+        store.insert(2, 2.0f32);
+        store.insert(1, 'd');
+
+        assert!(store.is_archetypal());
+        assert_eq!(*store.get::<char>(&1).unwrap(), 'd');
+        assert_eq!(*store.get::<f32>(&2).unwrap(), 2.0);
+        assert!(store.get::<f32>(&1).is_none());
+        assert!(store.contains_type_key::<std::rc::Rc<()>>(&2));
+        assert_eq!(
+            store.keys::<char>().into_iter().collect::<Vec<_>>(),
+            vec![0, 1, 2]
+        );
+        assert_eq!(store.iter_types().count(), 3);
+        assert_eq!(store.iter_key_untyped(&2).count(), 3);
+        assert_eq!(std::rc::Rc::strong_count(&value), 3);
+
+        *store.get_mut::<char>(&0).unwrap() = 'e';
+        store.remove::<char>(&2);
+        assert!(!store.contains_type_key::<char>(&2));
+        assert_eq!(*store.get::<f32>(&2).unwrap(), 2.0);
+        assert_eq!(*store.get::<char>(&0).unwrap(), 'e');
+
+        store.remove_key(&1);
+        assert!(!store.contains_key(&1));
+        assert_eq!(std::rc::Rc::strong_count(&value), 2);
+
+        store.clear::<std::rc::Rc<()>>();
+        assert_eq!(std::rc::Rc::strong_count(&value), 1);
+        assert!(store.contains_key(&2));
+        assert_eq!(store.keys_all().into_iter().collect::<Vec<_>>(), vec![0, 2]);
+
+        store.remove::<f32>(&2);
+        assert!(!store.contains_key(&2));
+
+        println!("\nStore: {:#?}\n", store);
+    }
+
+    #[test]
+    #[should_panic(expected = "must be registered via register_storage_for")]
+    fn register_storage_type_for_default_vec_map() {
+        let mut store = Store::<u32>::default();
+        store.register_storage_type_for::<i32>(StorageType::DefaultVecMap);
+    }
+
+    #[test]
+    #[should_panic]
+    fn register_storage_type_for_archetypes() {
+        let mut store = Store::<u32>::with_archetypes();
+        store.register_storage_type_for::<String>(StorageType::BTreeMap);
+    }
+
+    #[test]
+    fn unit_storage_drops() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        #[derive(Debug)]
+        struct Token;
+
+        impl Drop for Token {
+            fn drop(&mut self) {
+                DROPS.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        #[derive(Debug, Eq, PartialEq)]
+        struct Marker;
+
+        let mut store = Store::<u32>::default();
+        store.insert(0, Token);
+        store.insert(1, Token);
+        assert_eq!(
+            store.storage_type_for::<Token>(),
+            Some(StorageType::SparseVecMap)
+        );
+
+        store.remove::<Token>(&0);
+        store.remove::<Token>(&1);
+        store.remove::<Token>(&1);
+        assert_eq!(DROPS.load(Ordering::SeqCst), 2);
+        assert!(!store.contains_key(&1));
+
+        store.insert(0, Marker);
+        store.insert(1, Marker);
+        store.insert(2, Marker);
+        assert_eq!(store.storage_type_for::<Marker>(), Some(StorageType::Unit));
+        store.remove::<Marker>(&1);
+        assert!(!store.contains_type_key::<Marker>(&1));
+        assert!(store.types_of(&1).is_empty());
+
+        let mut markers = store.storage_mut::<Marker>().unwrap();
+        assert_eq!(markers.remove(&0), Some(Marker));
+        assert_eq!(markers.remove(&0), None);
+        assert_eq!(markers.iter_mut().count(), 1);
+    }
+
+    #[test]
+    #[should_panic]
+    fn unit_storage_needs_drop() {
+        #[derive(Debug)]
+        struct Token;
+
+        impl Drop for Token {
+            fn drop(&mut self) {}
+        }
+
+        let mut store = Store::<u32>::default();
+        store.register_storage_type_for::<Token>(StorageType::Unit);
+    }
+
+    #[test]
+    fn storage_thresholds() {
+        let mut store = Store::<u32>::default();
+        store.insert(0, ());
+        store.insert(0, 0u8);
+        store.insert(0, 0usize);
+
+        assert_eq!(store.storage_type_for::<()>(), Some(StorageType::Unit));
+        assert_eq!(
+            store.storage_type_for::<u8>(),
+            Some(StorageType::SparseVecMap)
+        );
+        assert_eq!(
+            store.storage_type_for::<usize>(),
+            Some(StorageType::DenseVecMap)
+        );
+
+        // Large values are moved by value on insert, so build them on the heap and insert them on a thread with room on its stack
+        let (largest, too_large, last_byte) = std::thread::Builder::new()
+            .stack_size(16 * 1024 * 1024)
+            .spawn(|| {
+                use std::convert::TryInto;
+
+                let largest: Box<[u8; 256 * 1024]> =
+                    vec![1u8; 256 * 1024].into_boxed_slice().try_into().unwrap();
+                let too_large: Box<[u8; 256 * 1024 + 1]> = vec![2u8; 256 * 1024 + 1]
+                    .into_boxed_slice()
+                    .try_into()
+                    .unwrap();
+
+                let mut store = Store::<u32>::default();
+                store.insert(0, *largest);
+                store.insert(0, *too_large);
+                let last_byte = store.get::<[u8; 256 * 1024 + 1]>(&0).unwrap()[256 * 1024];
+                (
+                    store.storage_type_for::<[u8; 256 * 1024]>(),
+                    store.storage_type_for::<[u8; 256 * 1024 + 1]>(),
+                    last_byte,
+                )
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+        assert_eq!(largest, Some(StorageType::DenseVecMap));
+        assert_eq!(too_large, Some(StorageType::HashMap));
+        assert_eq!(last_byte, 2);
+
+        let mut store = Store::<u32>::with_storage_thresholds(StorageThresholds {
+            sparse_vec_map: 2,
+            dense_vec_map: 4,
+        });
+        store.insert(0, 0u8);
+        store.insert(0, 0u16);
+        store.insert(0, 0u64);
+
+        assert_eq!(
+            store.storage_type_for::<u8>(),
+            Some(StorageType::SparseVecMap)
+        );
+        assert_eq!(
+            store.storage_type_for::<u16>(),
+            Some(StorageType::DenseVecMap)
+        );
+        assert_eq!(store.storage_type_for::<u64>(), Some(StorageType::HashMap));
+    }
+
+    #[test]
+    fn adaptive_storage() {
+        let mut store = Store::<u32>::default();
+        store.enable_adaptive_storage(0);
+
+        for key in (0..16).map(|i| i * 100) {
+            store.insert(key, key as u64);
+        }
+        assert_eq!(
+            store.storage_type_for::<u64>(),
+            Some(StorageType::DenseVecMap)
+        );
+
+        for key in (0..16).map(|i| i * 100) {
+            assert_eq!(*store.get::<u64>(&key).unwrap(), key as u64);
+        }
+
+        let statistics = store
+            .adaptive_storage()
+            .unwrap()
+            .statistics_for(&TypeKey::of::<u64>())
+            .unwrap();
+        assert_eq!(statistics.inserts(), 16);
+        assert_eq!(statistics.random_accesses(), 16);
+
+        store.adapt_storage();
+        assert_eq!(store.storage_type_for::<u64>(), Some(StorageType::HashMap));
+
+        // Idle types and types with too few accesses keep their storage
+        store.adapt_storage();
+        assert_eq!(store.storage_type_for::<u64>(), Some(StorageType::HashMap));
+        assert_eq!(*store.get::<u64>(&1500).unwrap(), 1500);
+        store.adapt_storage();
+        assert_eq!(store.storage_type_for::<u64>(), Some(StorageType::HashMap));
+
+        for _ in StoreQuery::<(u32, Ref<u64>)>::iter(&store) {}
+        store.adapt_storage();
+        assert_eq!(
+            store.storage_type_for::<u64>(),
+            Some(StorageType::DenseVecMap)
+        );
+        assert_eq!(store.keys::<u64>().into_iter().count(), 16);
+    }
+
+    /// Minimal arena storage: entries are appended to a Vec and found by linear scan
+    #[derive(Default)]
+    struct ArenaStorage<K, V> {
+        entries: Vec<(K, V)>,
+    }
+
+    impl<K, V> StorageTrait for ArenaStorage<K, V>
+    where
+        K: StoreKey,
+    {
+        type Key = K;
+        type Value = V;
+
+        fn get(&self, key: &K) -> Option<&V> {
+            self.entries.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+        }
+
+        fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+            self.entries
+                .iter_mut()
+                .find(|(k, _)| k == key)
+                .map(|(_, v)| v)
+        }
+
+        fn insert(&mut self, key: K, value: V) -> Option<V> {
+            let old = self.remove(&key);
+            self.entries.push((key, value));
+            old
+        }
+
+        fn remove(&mut self, key: &K) -> Option<V> {
+            let index = self.entries.iter().position(|(k, _)| k == key)?;
+            Some(self.entries.remove(index).1)
+        }
+
+        fn clear(&mut self) {
+            self.entries.clear()
+        }
+
+        fn iter_mut<'a>(
+            &'a mut self,
+            _: &'a KeySetOf<K>,
+        ) -> Box<dyn Iterator<Item = (K, &'a mut V)> + 'a> {
+            Box::new(self.entries.iter_mut().map(|(key, value)| (*key, value)))
+        }
+    }
+
+    #[test]
+    fn custom_storage() {
+        let mut store = Store::<u32>::default();
+        store.register_storage_for::<String, ArenaStorage<u32, String>>();
+
+        store.insert(3, "Hello".to_string());
+        store.insert(1, "World".to_string());
+        store.insert(1, 1);
+        store.insert(2, 2);
+
+        assert_eq!(
+            store.storage_type_for::<String>(),
+            Some(StorageType::Custom)
+        );
+        assert_eq!(&*store.get::<String>(&1).unwrap(), "World");
+
+        store.migrate_storage_for::<i32, ArenaStorage<u32, i32>>();
+        assert_eq!(store.storage_type_for::<i32>(), Some(StorageType::Custom));
+        assert_eq!(*store.get::<i32>(&2).unwrap(), 2);
+
+        let results: Vec<(u32, String, Option<i32>)> =
+            StoreQuery::<(u32, Ref<String>, Option<Ref<i32>>)>::iter(&store)
+                .map(|(key, string, int)| (key, string.clone(), int.map(|int| *int)))
+                .collect();
+        assert_eq!(
+            results,
+            vec![
+                (1, "World".to_string(), Some(1)),
+                (3, "Hello".to_string(), None)
+            ]
+        );
+
+        store.remove_key(&1);
+        assert!(!store.contains_type_key::<String>(&1));
+        assert!(!store.contains_type_key::<i32>(&1));
+
+        println!("\nStore: {:#?}\n", store);
+    }
+
+    #[test]
+    #[should_panic]
+    fn register_storage_type_for_custom() {
+        let mut store = Store::<u32>::default();
+        store.register_storage_type_for::<String>(StorageType::Custom);
+    }
 }
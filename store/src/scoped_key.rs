@@ -0,0 +1,145 @@
+use std::{
+    cell::{Ref, RefMut},
+    fmt::Debug,
+    ops::Deref,
+};
+
+use crate::{Store, StoreKey};
+
+/// Guard over a key created by Store::scoped_key, which removes the key and all of its values when dropped.
+/// Dereferences to the store for reads only, so the key cannot be removed and reused elsewhere while the guard holds it.
+pub struct ScopedKey<'a, Key>
+where
+    Key: StoreKey + 'static,
+{
+    store: &'a mut Store<Key>,
+    key: Key,
+}
+
+impl<'a, Key> ScopedKey<'a, Key>
+where
+    Key: StoreKey + 'static,
+{
+    pub(crate) fn new(store: &'a mut Store<Key>, key: Key) -> Self {
+        ScopedKey { store, key }
+    }
+
+    pub fn key(&self) -> Key {
+        self.key
+    }
+
+    pub fn insert<T>(&mut self, value: T)
+    where
+        T: Debug + 'static,
+    {
+        self.store.insert(self.key, value)
+    }
+
+    pub fn get<T>(&self) -> Option<Ref<'_, T>>
+    where
+        T: 'static,
+    {
+        self.store.get(&self.key)
+    }
+
+    pub fn get_mut<T>(&self) -> Option<RefMut<'_, T>>
+    where
+        T: 'static,
+    {
+        self.store.get_mut(&self.key)
+    }
+
+    pub fn remove<T>(&mut self)
+    where
+        T: Debug + 'static,
+    {
+        self.store.remove::<T>(&self.key)
+    }
+
+    /// Release the guard without removing the key, returning it
+    pub fn persist(self) -> Key {
+        let key = self.key;
+        std::mem::forget(self);
+        key
+    }
+}
+
+impl<'a, Key> Deref for ScopedKey<'a, Key>
+where
+    Key: StoreKey + 'static,
+{
+    type Target = Store<Key>;
+
+    fn deref(&self) -> &Self::Target {
+        self.store
+    }
+}
+
+impl<'a, Key> Drop for ScopedKey<'a, Key>
+where
+    Key: StoreKey + 'static,
+{
+    fn drop(&mut self) {
+        self.store.remove_key(&self.key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{GenerationalKey, Ref, Store, StoreQuery};
+
+    #[test]
+    fn scoped_key() {
+        let mut store = Store::<u32>::default();
+        let persistent = store.spawn(("persistent",));
+
+        let scratch = {
+            let mut scoped = store.scoped_key();
+            scoped.insert("scratch");
+            scoped.insert(1.5f32);
+            assert_eq!(*scoped.get::<&str>().unwrap(), "scratch");
+            *scoped.get_mut::<f32>().unwrap() += 1.0;
+            assert_eq!(*scoped.get::<f32>().unwrap(), 2.5);
+
+            // The store remains usable through the guard
+            let count = StoreQuery::<(u32, Ref<&str>)>::iter(&*scoped).count();
+            assert_eq!(count, 2);
+            scoped.key()
+        };
+
+        println!("{:?}", store);
+        assert!(!store.contains_key(&scratch));
+        assert_eq!(StoreQuery::<(u32, Ref<f32>)>::iter(&store).count(), 0);
+        assert!(store.contains_key(&persistent));
+
+        // Error paths drop the guard too
+        let result: Result<(), &str> = (|| {
+            let mut scoped = store.scoped_key();
+            scoped.insert("failing");
+            Err("request failed")?;
+            scoped.persist();
+            Ok(())
+        })();
+        assert!(result.is_err());
+        assert_eq!(StoreQuery::<(u32, Ref<&str>)>::iter(&store).count(), 1);
+
+        let mut scoped = store.scoped_key();
+        scoped.insert("kept");
+        let kept = scoped.persist();
+        assert_eq!(*store.get::<&str>(&kept).unwrap(), "kept");
+    }
+
+    #[test]
+    fn scoped_key_empty() {
+        let mut store = Store::<GenerationalKey>::default();
+
+        // A guard dropped before anything was inserted still frees its key
+        let unused = store.scoped_key().key();
+        assert!(!store.is_current(&unused));
+
+        let reused = store.create_key();
+        println!("{:?} {:?}", unused, reused);
+        assert_eq!(reused.index(), unused.index());
+        assert_ne!(reused, unused);
+    }
+}
@@ -1,4 +1,6 @@
-use crate::{BTreeMap, StorageTrait, StoreKey};
+use crate::{
+    key_range_is_empty, BTreeMap, KeyRange, KeySetOf, StorageTrait, StorageType, StoreKey,
+};
 
 /// BTreeMap-backed associative container for storing multiple types
 impl<Key, Value> StorageTrait for BTreeMap<Key, Value>
@@ -27,4 +29,23 @@ where
     fn clear(&mut self) {
         BTreeMap::clear(self)
     }
+
+    fn iter_mut<'a>(
+        &'a mut self,
+        _: &'a KeySetOf<Self::Key>,
+    ) -> Box<dyn Iterator<Item = (Self::Key, &'a mut Self::Value)> + 'a> {
+        Box::new(BTreeMap::iter_mut(self).map(|(key, value)| (*key, value)))
+    }
+
+    fn range_keys(&self, range: &KeyRange<Self::Key>) -> Option<Vec<Self::Key>> {
+        // BTreeMap::range panics on inverted ranges, which select nothing here as they do for key sets
+        if key_range_is_empty(range) {
+            return Some(vec![]);
+        }
+        Some(BTreeMap::range(self, *range).map(|(key, _)| *key).collect())
+    }
+
+    fn storage_type(&self) -> StorageType {
+        StorageType::BTreeMap
+    }
 }
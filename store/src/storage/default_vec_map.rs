@@ -0,0 +1,42 @@
+use crate::{DefaultVecMap, KeySet, KeySetOf, StorageTrait, StorageType, StoreKey};
+
+/// Vec-backed associative container with default-filled holes for storing multiple types
+impl<Key, Value> StorageTrait for DefaultVecMap<Key, Value>
+where
+    Key: StoreKey,
+    Value: Default,
+{
+    type Key = Key;
+    type Value = Value;
+
+    fn get(&self, key: &Self::Key) -> Option<&Self::Value> {
+        DefaultVecMap::get(self, key)
+    }
+
+    fn get_mut(&mut self, key: &Self::Key) -> Option<&mut Self::Value> {
+        DefaultVecMap::get_mut(self, key)
+    }
+
+    fn insert(&mut self, key: Self::Key, value: Self::Value) -> Option<Self::Value> {
+        DefaultVecMap::insert(self, key, value)
+    }
+
+    fn remove(&mut self, key: &Self::Key) -> Option<Self::Value> {
+        DefaultVecMap::remove(self, key)
+    }
+
+    fn clear(&mut self) {
+        DefaultVecMap::clear(self)
+    }
+
+    fn iter_mut<'a>(
+        &'a mut self,
+        keys: &'a KeySetOf<Self::Key>,
+    ) -> Box<dyn Iterator<Item = (Self::Key, &'a mut Self::Value)> + 'a> {
+        Box::new(DefaultVecMap::iter_mut(self).filter(move |(key, _)| keys.contains(key)))
+    }
+
+    fn storage_type(&self) -> StorageType {
+        StorageType::DefaultVecMap
+    }
+}
@@ -0,0 +1,53 @@
+use crate::{DenseVecMap, KeySetOf, Slices, SlicesMut, StorageTrait, StorageType, StoreKey};
+
+/// Sparse set associative container for storing multiple types
+impl<Key, Value> StorageTrait for DenseVecMap<Key, Value>
+where
+    Key: StoreKey,
+{
+    type Key = Key;
+    type Value = Value;
+
+    fn get(&self, key: &Self::Key) -> Option<&Self::Value> {
+        DenseVecMap::get(self, key)
+    }
+
+    fn get_mut(&mut self, key: &Self::Key) -> Option<&mut Self::Value> {
+        DenseVecMap::get_mut(self, key)
+    }
+
+    fn insert(&mut self, key: Self::Key, value: Self::Value) -> Option<Self::Value> {
+        DenseVecMap::insert(self, key, value)
+    }
+
+    fn remove(&mut self, key: &Self::Key) -> Option<Self::Value> {
+        DenseVecMap::remove(self, key)
+    }
+
+    fn clear(&mut self) {
+        DenseVecMap::clear(self)
+    }
+
+    fn iter_mut<'a>(
+        &'a mut self,
+        _: &'a KeySetOf<Self::Key>,
+    ) -> Box<dyn Iterator<Item = (Self::Key, &'a mut Self::Value)> + 'a> {
+        Box::new(DenseVecMap::iter_mut(self).map(|(key, value)| (*key, value)))
+    }
+
+    fn as_slices(&self) -> Option<Slices<'_, Self::Key, Self::Value>> {
+        Some(DenseVecMap::as_slices(self))
+    }
+
+    fn as_slices_mut(&mut self) -> Option<SlicesMut<'_, Self::Key, Self::Value>> {
+        Some(DenseVecMap::as_slices_mut(self))
+    }
+
+    fn storage_type(&self) -> StorageType {
+        StorageType::DenseVecMap
+    }
+
+    fn dense_keys(&self) -> Option<&[Self::Key]> {
+        Some(DenseVecMap::keys(self))
+    }
+}
@@ -0,0 +1,120 @@
+use crate::{
+    BTreeMap, DenseVecMap, HashMap, InsertionOrderedMap, KeyRange, KeySetOf, PagedSparseVecMap,
+    Slices, SlicesMut, SparseVecMap, StorageTrait, StorageType, StoreKey, UnitStorage,
+};
+
+/// Storage for a single value type, with the container chosen at runtime.
+///
+/// Built-in containers are held inline and dispatched by match, so typed access pays no virtual call here.
+/// Custom storages are boxed behind the StorageTrait vtable.
+pub enum DynStorage<Key, Value>
+where
+    Key: StoreKey,
+{
+    BTreeMap(BTreeMap<Key, Value>),
+    HashMap(HashMap<Key, Value>),
+    SparseVecMap(SparseVecMap<Key, Value>),
+    PagedSparseVecMap(PagedSparseVecMap<Key, Value>),
+    DenseVecMap(DenseVecMap<Key, Value>),
+    InsertionOrderedMap(InsertionOrderedMap<Key, Value>),
+    Unit(UnitStorage<Key, Value>),
+    Custom(Box<dyn StorageTrait<Key = Key, Value = Value>>),
+}
+
+/// Apply the same expression to whichever container a DynStorage holds
+macro_rules! dispatch {
+    ($storage:expr, $inner:ident => $body:expr) => {
+        match $storage {
+            DynStorage::BTreeMap($inner) => $body,
+            DynStorage::HashMap($inner) => $body,
+            DynStorage::SparseVecMap($inner) => $body,
+            DynStorage::PagedSparseVecMap($inner) => $body,
+            DynStorage::DenseVecMap($inner) => $body,
+            DynStorage::InsertionOrderedMap($inner) => $body,
+            DynStorage::Unit($inner) => $body,
+            DynStorage::Custom($inner) => $body,
+        }
+    };
+}
+
+impl<Key, Value> DynStorage<Key, Value>
+where
+    Key: StoreKey,
+{
+    /// Create an empty built-in container.
+    /// DefaultVecMap and Custom storages are supplied via StoreBacking::with_storage, and rejected at registration.
+    pub fn new(storage_type: StorageType) -> Self {
+        match storage_type {
+            StorageType::BTreeMap => DynStorage::BTreeMap(BTreeMap::new()),
+            StorageType::HashMap => DynStorage::HashMap(HashMap::default()),
+            StorageType::SparseVecMap => DynStorage::SparseVecMap(SparseVecMap::new()),
+            StorageType::PagedSparseVecMap => {
+                DynStorage::PagedSparseVecMap(PagedSparseVecMap::new())
+            }
+            StorageType::DenseVecMap => DynStorage::DenseVecMap(DenseVecMap::new()),
+            StorageType::InsertionOrderedMap => {
+                DynStorage::InsertionOrderedMap(InsertionOrderedMap::new())
+            }
+            StorageType::Unit => DynStorage::Unit(UnitStorage::default()),
+            StorageType::DefaultVecMap | StorageType::Custom => unreachable!(
+                "{:?} storage must be created via StoreBacking::with_storage",
+                storage_type
+            ),
+        }
+    }
+}
+
+impl<Key, Value> StorageTrait for DynStorage<Key, Value>
+where
+    Key: StoreKey,
+{
+    type Key = Key;
+    type Value = Value;
+
+    fn get(&self, key: &Self::Key) -> Option<&Self::Value> {
+        dispatch!(self, storage => StorageTrait::get(storage, key))
+    }
+
+    fn get_mut(&mut self, key: &Self::Key) -> Option<&mut Self::Value> {
+        dispatch!(self, storage => StorageTrait::get_mut(storage, key))
+    }
+
+    fn insert(&mut self, key: Self::Key, value: Self::Value) -> Option<Self::Value> {
+        dispatch!(self, storage => StorageTrait::insert(storage, key, value))
+    }
+
+    fn remove(&mut self, key: &Self::Key) -> Option<Self::Value> {
+        dispatch!(self, storage => StorageTrait::remove(storage, key))
+    }
+
+    fn clear(&mut self) {
+        dispatch!(self, storage => StorageTrait::clear(storage))
+    }
+
+    fn iter_mut<'a>(
+        &'a mut self,
+        keys: &'a KeySetOf<Self::Key>,
+    ) -> Box<dyn Iterator<Item = (Self::Key, &'a mut Self::Value)> + 'a> {
+        dispatch!(self, storage => StorageTrait::iter_mut(storage, keys))
+    }
+
+    fn storage_type(&self) -> StorageType {
+        dispatch!(self, storage => StorageTrait::storage_type(storage))
+    }
+
+    fn dense_keys(&self) -> Option<&[Self::Key]> {
+        dispatch!(self, storage => StorageTrait::dense_keys(storage))
+    }
+
+    fn as_slices(&self) -> Option<Slices<'_, Self::Key, Self::Value>> {
+        dispatch!(self, storage => StorageTrait::as_slices(storage))
+    }
+
+    fn as_slices_mut(&mut self) -> Option<SlicesMut<'_, Self::Key, Self::Value>> {
+        dispatch!(self, storage => StorageTrait::as_slices_mut(storage))
+    }
+
+    fn range_keys(&self, range: &KeyRange<Self::Key>) -> Option<Vec<Self::Key>> {
+        dispatch!(self, storage => StorageTrait::range_keys(storage, range))
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{HashMap, StorageTrait, StoreKey};
+use crate::{HashMap, KeySetOf, StorageTrait, StorageType, StoreKey};
 
 /// HashMap-backed associative container for storing multiple types
 impl<Key, Value> StorageTrait for HashMap<Key, Value>
@@ -27,4 +27,15 @@ where
     fn clear(&mut self) {
         HashMap::clear(self)
     }
+
+    fn iter_mut<'a>(
+        &'a mut self,
+        _: &'a KeySetOf<Self::Key>,
+    ) -> Box<dyn Iterator<Item = (Self::Key, &'a mut Self::Value)> + 'a> {
+        Box::new(HashMap::iter_mut(self).map(|(key, value)| (*key, value)))
+    }
+
+    fn storage_type(&self) -> StorageType {
+        StorageType::HashMap
+    }
 }
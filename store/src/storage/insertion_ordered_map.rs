@@ -0,0 +1,55 @@
+use crate::{
+    InsertionOrderedMap, KeySetOf, Slices, SlicesMut, StorageTrait, StorageType, StoreKey,
+};
+
+/// Insertion-ordered associative container for storing multiple types
+impl<Key, Value> StorageTrait for InsertionOrderedMap<Key, Value>
+where
+    Key: StoreKey,
+{
+    type Key = Key;
+    type Value = Value;
+
+    fn get(&self, key: &Self::Key) -> Option<&Self::Value> {
+        InsertionOrderedMap::get(self, key)
+    }
+
+    fn get_mut(&mut self, key: &Self::Key) -> Option<&mut Self::Value> {
+        InsertionOrderedMap::get_mut(self, key)
+    }
+
+    fn insert(&mut self, key: Self::Key, value: Self::Value) -> Option<Self::Value> {
+        InsertionOrderedMap::insert(self, key, value)
+    }
+
+    fn remove(&mut self, key: &Self::Key) -> Option<Self::Value> {
+        InsertionOrderedMap::remove(self, key)
+    }
+
+    fn clear(&mut self) {
+        InsertionOrderedMap::clear(self)
+    }
+
+    fn iter_mut<'a>(
+        &'a mut self,
+        _: &'a KeySetOf<Self::Key>,
+    ) -> Box<dyn Iterator<Item = (Self::Key, &'a mut Self::Value)> + 'a> {
+        Box::new(InsertionOrderedMap::iter_mut(self).map(|(key, value)| (*key, value)))
+    }
+
+    fn as_slices(&self) -> Option<Slices<'_, Self::Key, Self::Value>> {
+        Some(InsertionOrderedMap::as_slices(self))
+    }
+
+    fn as_slices_mut(&mut self) -> Option<SlicesMut<'_, Self::Key, Self::Value>> {
+        Some(InsertionOrderedMap::as_slices_mut(self))
+    }
+
+    fn storage_type(&self) -> StorageType {
+        StorageType::InsertionOrderedMap
+    }
+
+    fn dense_keys(&self) -> Option<&[Self::Key]> {
+        Some(InsertionOrderedMap::keys(self))
+    }
+}
@@ -1,14 +1,21 @@
 mod btree_map;
+mod default_vec_map;
+mod dense_vec_map;
+mod dyn_storage;
 mod hash_map;
+mod insertion_ordered_map;
+mod paged_sparse_vec_map;
 mod sparse_vec_map;
+mod unit_storage;
 
-pub use btree_map::*;
-pub use hash_map::*;
-pub use sparse_vec_map::*;
+pub use dyn_storage::*;
+pub use unit_storage::*;
 
-use crate::StoreKey;
+use crate::{KeyRange, KeySetOf, StorageType, StoreKey};
 
 /// Trait for an associative container that can store multiple types
+///
+/// Distinct keys must map to distinct values, except for zero-sized values.
 pub trait StorageTrait {
     type Key: StoreKey;
     type Value;
@@ -18,4 +25,96 @@ pub trait StorageTrait {
     fn insert(&mut self, key: Self::Key, value: Self::Value) -> Option<Self::Value>;
     fn remove(&mut self, key: &Self::Key) -> Option<Self::Value>;
     fn clear(&mut self);
+
+    /// Iterate mutably over the stored values.
+    /// `keys` holds the keys present in the owning backing, for storages that do not track membership themselves.
+    fn iter_mut<'a>(
+        &'a mut self,
+        keys: &'a KeySetOf<Self::Key>,
+    ) -> Box<dyn Iterator<Item = (Self::Key, &'a mut Self::Value)> + 'a>;
+
+    fn storage_type(&self) -> StorageType {
+        StorageType::Custom
+    }
+
+    /// Keys in the order their values are packed, for storages that keep them contiguous.
+    /// Queries driven by a storage that returns Some iterate in this order.
+    fn dense_keys(&self) -> Option<&[Self::Key]> {
+        None
+    }
+
+    /// Keys and values as parallel packed slices, for storages that keep them contiguous
+    fn as_slices(&self) -> Option<Slices<'_, Self::Key, Self::Value>> {
+        None
+    }
+
+    fn as_slices_mut(&mut self) -> Option<SlicesMut<'_, Self::Key, Self::Value>> {
+        None
+    }
+
+    /// Keys within a range in ascending order, for storages that keep their keys sorted
+    fn range_keys(&self, _range: &KeyRange<Self::Key>) -> Option<Vec<Self::Key>> {
+        None
+    }
+}
+
+/// Parallel key and value slices of a dense storage
+pub type Slices<'a, Key, Value> = (&'a [Key], &'a [Value]);
+
+pub type SlicesMut<'a, Key, Value> = (&'a [Key], &'a mut [Value]);
+
+/// Boxed storages delegate to the storage they hold, as custom storages inside DynStorage do
+impl<Key, Value> StorageTrait for Box<dyn StorageTrait<Key = Key, Value = Value>>
+where
+    Key: StoreKey,
+{
+    type Key = Key;
+    type Value = Value;
+
+    fn get(&self, key: &Self::Key) -> Option<&Self::Value> {
+        (**self).get(key)
+    }
+
+    fn get_mut(&mut self, key: &Self::Key) -> Option<&mut Self::Value> {
+        (**self).get_mut(key)
+    }
+
+    fn insert(&mut self, key: Self::Key, value: Self::Value) -> Option<Self::Value> {
+        (**self).insert(key, value)
+    }
+
+    fn remove(&mut self, key: &Self::Key) -> Option<Self::Value> {
+        (**self).remove(key)
+    }
+
+    fn clear(&mut self) {
+        (**self).clear()
+    }
+
+    fn iter_mut<'a>(
+        &'a mut self,
+        keys: &'a KeySetOf<Self::Key>,
+    ) -> Box<dyn Iterator<Item = (Self::Key, &'a mut Self::Value)> + 'a> {
+        (**self).iter_mut(keys)
+    }
+
+    fn storage_type(&self) -> StorageType {
+        (**self).storage_type()
+    }
+
+    fn dense_keys(&self) -> Option<&[Self::Key]> {
+        (**self).dense_keys()
+    }
+
+    fn as_slices(&self) -> Option<Slices<'_, Self::Key, Self::Value>> {
+        (**self).as_slices()
+    }
+
+    fn as_slices_mut(&mut self) -> Option<SlicesMut<'_, Self::Key, Self::Value>> {
+        (**self).as_slices_mut()
+    }
+
+    fn range_keys(&self, range: &KeyRange<Self::Key>) -> Option<Vec<Self::Key>> {
+        (**self).range_keys(range)
+    }
 }
@@ -0,0 +1,41 @@
+use crate::{KeySetOf, PagedSparseVecMap, StorageTrait, StorageType, StoreKey};
+
+/// Paged Vec-backed associative container for storing multiple types
+impl<Key, Value> StorageTrait for PagedSparseVecMap<Key, Value>
+where
+    Key: StoreKey,
+{
+    type Key = Key;
+    type Value = Value;
+
+    fn get(&self, key: &Self::Key) -> Option<&Self::Value> {
+        PagedSparseVecMap::get(self, key)
+    }
+
+    fn get_mut(&mut self, key: &Self::Key) -> Option<&mut Self::Value> {
+        PagedSparseVecMap::get_mut(self, key)
+    }
+
+    fn insert(&mut self, key: Self::Key, value: Self::Value) -> Option<Self::Value> {
+        PagedSparseVecMap::insert(self, key, value)
+    }
+
+    fn remove(&mut self, key: &Self::Key) -> Option<Self::Value> {
+        PagedSparseVecMap::remove(self, key)
+    }
+
+    fn clear(&mut self) {
+        PagedSparseVecMap::clear(self)
+    }
+
+    fn iter_mut<'a>(
+        &'a mut self,
+        _: &'a KeySetOf<Self::Key>,
+    ) -> Box<dyn Iterator<Item = (Self::Key, &'a mut Self::Value)> + 'a> {
+        Box::new(PagedSparseVecMap::iter_mut(self))
+    }
+
+    fn storage_type(&self) -> StorageType {
+        StorageType::PagedSparseVecMap
+    }
+}
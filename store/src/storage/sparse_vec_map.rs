@@ -1,4 +1,4 @@
-use crate::{SparseVecMap, StorageTrait, StoreKey};
+use crate::{KeySetOf, SparseVecMap, StorageTrait, StorageType, StoreKey};
 
 /// Vec-backed associative container for storing multiple types
 impl<Key, Value> StorageTrait for SparseVecMap<Key, Value>
@@ -9,24 +9,33 @@ where
     type Value = Value;
 
     fn get(&self, key: &Self::Key) -> Option<&Self::Value> {
-        Some(SparseVecMap::get(self, key))
+        SparseVecMap::get(self, key)
     }
 
     fn get_mut(&mut self, key: &Self::Key) -> Option<&mut Self::Value> {
-        Some(SparseVecMap::get_mut(self, key))
+        SparseVecMap::get_mut(self, key)
     }
 
     fn insert(&mut self, key: Self::Key, value: Self::Value) -> Option<Self::Value> {
-        SparseVecMap::insert(self, key, value, false);
-        None
+        SparseVecMap::insert(self, key, value)
     }
 
     fn remove(&mut self, key: &Self::Key) -> Option<Self::Value> {
-        SparseVecMap::remove(self, key);
-        None
+        SparseVecMap::remove(self, key)
     }
 
     fn clear(&mut self) {
         SparseVecMap::clear(self)
     }
+
+    fn iter_mut<'a>(
+        &'a mut self,
+        _: &'a KeySetOf<Self::Key>,
+    ) -> Box<dyn Iterator<Item = (Self::Key, &'a mut Self::Value)> + 'a> {
+        Box::new(SparseVecMap::iter_mut(self))
+    }
+
+    fn storage_type(&self) -> StorageType {
+        StorageType::SparseVecMap
+    }
 }
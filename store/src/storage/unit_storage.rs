@@ -0,0 +1,74 @@
+use std::{fmt::Debug, marker::PhantomData};
+
+use crate::{KeySet, KeySetOf, StorageTrait, StorageType, StoreKey};
+
+/// Storage for zero-sized types that need no drop, held as a Vec of ZSTs which never allocates.
+///
+/// Key membership is expected to be tracked externally, as by StoreBackingRefCell's KeySet.
+/// Zero-sized values carry no state, so values are not tied to keys: each inserted value is handed back exactly once, by whichever removal comes first.
+pub struct UnitStorage<K, V> {
+    values: Vec<V>,
+    _phantom_data: PhantomData<K>,
+}
+
+impl<K, V> Debug for UnitStorage<K, V>
+where
+    V: Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("UnitStorage")
+            .field(&self.values.first())
+            .finish()
+    }
+}
+
+impl<K, V> Default for UnitStorage<K, V> {
+    fn default() -> Self {
+        UnitStorage {
+            values: Vec::new(),
+            _phantom_data: PhantomData,
+        }
+    }
+}
+
+/// Zero-sized associative container for storing marker types
+impl<Key, Value> StorageTrait for UnitStorage<Key, Value>
+where
+    Key: StoreKey,
+{
+    type Key = Key;
+    type Value = Value;
+
+    fn get(&self, _: &Self::Key) -> Option<&Self::Value> {
+        self.values.first()
+    }
+
+    fn get_mut(&mut self, _: &Self::Key) -> Option<&mut Self::Value> {
+        self.values.first_mut()
+    }
+
+    fn insert(&mut self, _: Self::Key, value: Self::Value) -> Option<Self::Value> {
+        self.values.push(value);
+        None
+    }
+
+    fn remove(&mut self, _: &Self::Key) -> Option<Self::Value> {
+        self.values.pop()
+    }
+
+    fn clear(&mut self) {
+        self.values.clear()
+    }
+
+    /// Hands out a distinct value per key
+    fn iter_mut<'a>(
+        &'a mut self,
+        keys: &'a KeySetOf<Self::Key>,
+    ) -> Box<dyn Iterator<Item = (Self::Key, &'a mut Self::Value)> + 'a> {
+        Box::new(keys.iter().zip(self.values.iter_mut()))
+    }
+
+    fn storage_type(&self) -> StorageType {
+        StorageType::Unit
+    }
+}
@@ -0,0 +1,553 @@
+use std::cell::{Cell, Ref, RefMut};
+
+use crate::{
+    key_allocator::KeyAllocator, key_types::KeyTypes, DynStorage, KeySet, KeySetOf, StorageTrait,
+    StoreKey, TypeKey,
+};
+
+/// Shared view over the backing of a single type, borrowed once for the lifetime of the view.
+/// Accesses through the view are not recorded for adaptive storage.
+pub struct StorageRef<'a, Key, T>
+where
+    Key: StoreKey,
+{
+    keys: Ref<'a, KeySetOf<Key>>,
+    values: Ref<'a, DynStorage<Key, T>>,
+    len: usize,
+    key_allocator: &'a KeyAllocator<Key>,
+}
+
+impl<'a, Key, T> StorageRef<'a, Key, T>
+where
+    Key: StoreKey,
+{
+    pub(crate) fn new(
+        keys: Ref<'a, KeySetOf<Key>>,
+        values: Ref<'a, DynStorage<Key, T>>,
+        len: usize,
+        key_allocator: &'a KeyAllocator<Key>,
+    ) -> Self {
+        StorageRef {
+            keys,
+            values,
+            len,
+            key_allocator,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn contains_key(&self, key: &Key) -> bool {
+        self.key_allocator.resolve(key).is_some() && self.keys.contains(key)
+    }
+
+    pub fn get(&self, key: &Key) -> Option<&T> {
+        if self.contains_key(key) {
+            self.values.get(&Key::from_index(key.to_index()))
+        } else {
+            None
+        }
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = Key> + '_ {
+        self.keys
+            .iter()
+            .map(move |key| self.key_allocator.current(key.to_index()))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Key, &T)> {
+        self.keys.iter().map(move |key| {
+            let value = self.values.get(&key).unwrap();
+            (self.key_allocator.current(key.to_index()), value)
+        })
+    }
+}
+
+/// Exclusive view over the backing of a single type, borrowed once for the lifetime of the view.
+/// Accesses through the view are not recorded for adaptive storage.
+pub struct StorageMut<'a, Key, T>
+where
+    Key: StoreKey,
+{
+    keys: RefMut<'a, KeySetOf<Key>>,
+    values: RefMut<'a, DynStorage<Key, T>>,
+    len: &'a Cell<usize>,
+    key_types: &'a mut KeyTypes<Key>,
+    key_allocator: &'a KeyAllocator<Key>,
+}
+
+impl<'a, Key, T> StorageMut<'a, Key, T>
+where
+    Key: StoreKey,
+    T: 'static,
+{
+    pub(crate) fn new(
+        keys: RefMut<'a, KeySetOf<Key>>,
+        values: RefMut<'a, DynStorage<Key, T>>,
+        len: &'a Cell<usize>,
+        key_types: &'a mut KeyTypes<Key>,
+        key_allocator: &'a KeyAllocator<Key>,
+    ) -> Self {
+        StorageMut {
+            keys,
+            values,
+            len,
+            key_types,
+            key_allocator,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len.get()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len.get() == 0
+    }
+
+    pub fn contains_key(&self, key: &Key) -> bool {
+        self.key_allocator.resolve(key).is_some() && self.keys.contains(key)
+    }
+
+    pub fn get(&self, key: &Key) -> Option<&T> {
+        if self.contains_key(key) {
+            self.values.get(&Key::from_index(key.to_index()))
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, key: &Key) -> Option<&mut T> {
+        if self.contains_key(key) {
+            self.values.get_mut(&Key::from_index(key.to_index()))
+        } else {
+            None
+        }
+    }
+
+    /// Insert a value for a key, panicking if the key is a stale handle
+    pub fn insert(&mut self, key: Key, value: T) -> Option<T> {
+        let key = self
+            .key_allocator
+            .resolve(&key)
+            .unwrap_or_else(|| panic!("Cannot insert through stale key {:?}", key));
+
+        if self.keys.contains(&key) {
+            Some(std::mem::replace(self.values.get_mut(&key).unwrap(), value))
+        } else {
+            self.keys.insert(&key);
+            self.values.insert(key, value);
+            self.len.set(self.len.get() + 1);
+            self.key_types.add(key, TypeKey::of::<T>());
+            None
+        }
+    }
+
+    pub fn remove(&mut self, key: &Key) -> Option<T> {
+        let key = &self.key_allocator.resolve(key)?;
+        if self.keys.remove(key) {
+            self.len.set(self.len.get() - 1);
+            self.key_types.remove(key, &TypeKey::of::<T>());
+            self.values.remove(key)
+        } else {
+            None
+        }
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = Key> + '_ {
+        self.keys
+            .iter()
+            .map(move |key| self.key_allocator.current(key.to_index()))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Key, &T)> {
+        self.keys.iter().map(move |key| {
+            let value = self.values.get(&key).unwrap();
+            (self.key_allocator.current(key.to_index()), value)
+        })
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Key, &mut T)> {
+        let key_allocator = self.key_allocator;
+        self.values
+            .iter_mut(&self.keys)
+            .map(move |(key, value)| (key_allocator.current(key.to_index()), value))
+    }
+}
+
+/// Shared view over the packed keys and values of a single type held in dense storage
+pub struct Column<'a, Key, T>
+where
+    Key: StoreKey,
+{
+    values: Ref<'a, DynStorage<Key, T>>,
+}
+
+impl<'a, Key, T> Column<'a, Key, T>
+where
+    Key: StoreKey,
+{
+    pub(crate) fn new(values: Ref<'a, DynStorage<Key, T>>) -> Option<Self> {
+        values.as_slices()?;
+        Some(Column { values })
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys().is_empty()
+    }
+
+    /// Keys as held by the storage, by index alone; Store::key_at recovers a key's current handle
+    pub fn keys(&self) -> &[Key] {
+        self.as_slices().0
+    }
+
+    pub fn values(&self) -> &[T] {
+        self.as_slices().1
+    }
+
+    pub fn as_slices(&self) -> (&[Key], &[T]) {
+        self.values.as_slices().unwrap()
+    }
+}
+
+/// Exclusive view over the packed keys and values of a single type held in dense storage.
+/// Values may be modified in place, but keys cannot be added or removed.
+pub struct ColumnMut<'a, Key, T>
+where
+    Key: StoreKey,
+{
+    values: RefMut<'a, DynStorage<Key, T>>,
+}
+
+impl<'a, Key, T> ColumnMut<'a, Key, T>
+where
+    Key: StoreKey,
+{
+    pub(crate) fn new(values: RefMut<'a, DynStorage<Key, T>>) -> Option<Self> {
+        values.as_slices()?;
+        Some(ColumnMut { values })
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys().is_empty()
+    }
+
+    /// Keys as held by the storage, by index alone; Store::key_at recovers a key's current handle
+    pub fn keys(&self) -> &[Key] {
+        self.as_slices().0
+    }
+
+    pub fn values(&self) -> &[T] {
+        self.as_slices().1
+    }
+
+    pub fn values_mut(&mut self) -> &mut [T] {
+        self.as_slices_mut().1
+    }
+
+    pub fn as_slices(&self) -> (&[Key], &[T]) {
+        self.values.as_slices().unwrap()
+    }
+
+    pub fn as_slices_mut(&mut self) -> (&[Key], &mut [T]) {
+        self.values.as_slices_mut().unwrap()
+    }
+}
+
+/// A column that can take part in a zipped walk, yielding its key slice and an iterator over its values
+pub trait ZipColumn<'c, Key> {
+    type Iter: Iterator + 'c;
+
+    fn into_slices(self) -> (&'c [Key], Self::Iter);
+}
+
+impl<'c, 'a, Key, T> ZipColumn<'c, Key> for &'c Column<'a, Key, T>
+where
+    Key: StoreKey,
+{
+    type Iter = std::slice::Iter<'c, T>;
+
+    fn into_slices(self) -> (&'c [Key], Self::Iter) {
+        let (keys, values) = self.as_slices();
+        (keys, values.iter())
+    }
+}
+
+impl<'c, 'a, Key, T> ZipColumn<'c, Key> for &'c ColumnMut<'a, Key, T>
+where
+    Key: StoreKey,
+{
+    type Iter = std::slice::Iter<'c, T>;
+
+    fn into_slices(self) -> (&'c [Key], Self::Iter) {
+        let (keys, values) = self.as_slices();
+        (keys, values.iter())
+    }
+}
+
+impl<'c, 'a, Key, T> ZipColumn<'c, Key> for &'c mut ColumnMut<'a, Key, T>
+where
+    Key: StoreKey,
+{
+    type Iter = std::slice::IterMut<'c, T>;
+
+    fn into_slices(self) -> (&'c [Key], Self::Iter) {
+        let (keys, values) = self.as_slices_mut();
+        (keys, values.iter_mut())
+    }
+}
+
+/// Walk a tuple of columns in lockstep, yielding each key with its value from every column.
+/// Returns None unless every column holds the same keys in the same order.
+pub trait ZipColumns<'c, Key> {
+    type Iter: Iterator;
+
+    fn zip_columns(self) -> Option<Self::Iter>;
+}
+
+/// Lockstep walk over column slices, built on std's Zip so the walk compiles down to indexed slice accesses
+pub struct ZipColumnsIter<I>(I);
+
+/// Nested Zip of a key slice iterator with every column's value iterator
+macro_rules! zip_type {
+    ($zip:ty;) => { $zip };
+    ($zip:ty; $first:ty $(, $rest:ty)*) => {
+        zip_type!(std::iter::Zip<$zip, $first>; $($rest),*)
+    };
+}
+
+/// Pattern destructuring an item of zip_type, binding the key and each column's value
+macro_rules! zip_pattern {
+    ([$($pattern:tt)*];) => { $($pattern)* };
+    ([$($pattern:tt)*]; $first:ident $(, $rest:ident)*) => {
+        zip_pattern!([($($pattern)*, $first)]; $($rest),*)
+    };
+}
+
+macro_rules! impl_zip_columns {
+    ($first:ident $first_value:ident $(, $rest:ident $rest_value:ident)+) => {
+        impl<'c, Key, $first, $($rest),+> ZipColumns<'c, Key> for ($first, $($rest),+)
+        where
+            Key: StoreKey + 'c,
+            $first: ZipColumn<'c, Key>,
+            $($rest: ZipColumn<'c, Key>,)+
+        {
+            type Iter = ZipColumnsIter<zip_type!(std::slice::Iter<'c, Key>; $first::Iter $(, $rest::Iter)+)>;
+
+            #[allow(non_snake_case)]
+            fn zip_columns(self) -> Option<Self::Iter> {
+                let ($first, $($rest),+) = self;
+                let (keys, $first) = $first.into_slices();
+                $(
+                    let (other_keys, $rest) = $rest.into_slices();
+                    if other_keys != keys {
+                        return None;
+                    }
+                )+
+
+                let zip = keys.iter().zip($first);
+                $(
+                    let zip = zip.zip($rest);
+                )+
+                Some(ZipColumnsIter(zip))
+            }
+        }
+
+        impl<'c, Key, $first, $($rest),+> Iterator
+            for ZipColumnsIter<zip_type!(std::slice::Iter<'c, Key>; $first $(, $rest)+)>
+        where
+            Key: Copy + 'c,
+            $first: Iterator,
+            $($rest: Iterator,)+
+        {
+            type Item = (Key, $first::Item, $($rest::Item,)+);
+
+            fn next(&mut self) -> Option<Self::Item> {
+                let zip_pattern!([key]; $first_value $(, $rest_value)+) = self.0.next()?;
+                Some((*key, $first_value, $($rest_value,)+))
+            }
+
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                self.0.size_hint()
+            }
+        }
+    };
+}
+
+impl_zip_columns!(A a, B b);
+impl_zip_columns!(A a, B b, C c);
+impl_zip_columns!(A a, B b, C c, D d);
+
+#[cfg(test)]
+mod tests {
+    use crate::{DefaultVecMap, StorageType, Store, ZipColumns};
+
+    #[test]
+    fn storage() {
+        let mut store = Store::<u32>::default();
+        store.insert(0, 'a');
+        store.insert(3, 'b');
+        store.insert(5, 'c');
+
+        let chars = store.storage::<char>().unwrap();
+        assert_eq!(chars.len(), 3);
+        assert_eq!(chars.get(&3), Some(&'b'));
+        assert_eq!(chars.get(&4), None);
+        assert_eq!(
+            chars.iter().collect::<Vec<_>>(),
+            vec![(0, &'a'), (3, &'b'), (5, &'c')]
+        );
+
+        assert!(store.storage::<i32>().is_none());
+
+        let mut archetypal_store = Store::<u32>::with_archetypes();
+        archetypal_store.insert(0, 'a');
+        assert!(archetypal_store.storage::<char>().is_none());
+        assert!(archetypal_store.storage_mut::<char>().is_none());
+    }
+
+    #[test]
+    fn storage_mut() {
+        #[derive(Debug, Copy, Clone, Eq, PartialEq)]
+        struct Marker;
+
+        let mut store = Store::<u32>::default();
+        store.register_storage_type_for::<String>(StorageType::BTreeMap);
+        store.register_storage_for::<i32, DefaultVecMap<u32, i32>>();
+
+        {
+            let mut strings = store.storage_mut::<String>().unwrap();
+            assert_eq!(strings.insert(1, "Hello".into()), None);
+            assert_eq!(strings.insert(4, "World".into()), None);
+            assert_eq!(strings.insert(1, "Goodbye".into()), Some("Hello".into()));
+
+            for (_, string) in strings.iter_mut() {
+                string.push('!');
+            }
+
+            assert_eq!(strings.len(), 2);
+            assert_eq!(strings.remove(&4), Some("World!".into()));
+            assert_eq!(strings.remove(&4), None);
+            assert_eq!(strings.len(), 1);
+            assert_eq!(strings.keys().collect::<Vec<_>>(), vec![1]);
+        }
+
+        {
+            let mut ints = store.storage_mut::<i32>().unwrap();
+            ints.insert(4, 4);
+            ints.insert(2, 2);
+            for (key, int) in ints.iter_mut() {
+                *int += key as i32;
+            }
+            assert_eq!(ints.iter().collect::<Vec<_>>(), vec![(2, &4), (4, &8)]);
+            *ints.get_mut(&2).unwrap() = 0;
+        }
+
+        {
+            let mut markers = store.storage_mut::<Marker>().unwrap();
+            markers.insert(0, Marker);
+            markers.insert(3, Marker);
+            assert_eq!(markers.iter_mut().count(), 2);
+        }
+
+        assert_eq!(&*store.get::<String>(&1).unwrap(), "Goodbye!");
+        assert_eq!(*store.get::<i32>(&2).unwrap(), 0);
+        assert!(store.get::<i32>(&3).is_none());
+        assert_eq!(store.types_of(&3).len(), 1);
+        assert!(store.contains_key(&4));
+        assert!(!store.contains_type_key::<String>(&4));
+
+        assert_eq!(store.storage::<String>().unwrap().len(), 1);
+        store.remove::<i32>(&4);
+        assert_eq!(store.storage::<i32>().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn column() {
+        let mut store = Store::<u32>::default();
+        store.register_storage_type_for::<f32>(StorageType::DenseVecMap);
+        store.register_storage_type_for::<String>(StorageType::BTreeMap);
+        store.insert(7, 1.0f32);
+        store.insert(2, 2.0f32);
+        store.insert(5, 3.0f32);
+        store.insert(2, String::from("Hello"));
+
+        {
+            let mut floats = store.column_mut::<f32>().unwrap();
+            for value in floats.values_mut() {
+                *value *= 2.0;
+            }
+        }
+
+        let floats = store.column::<f32>().unwrap();
+        println!("{:?}", floats.as_slices());
+        assert_eq!(floats.keys(), &[7, 2, 5]);
+        assert_eq!(floats.values(), &[2.0, 4.0, 6.0]);
+        assert_eq!(floats.len(), 3);
+
+        assert!(store.column::<String>().is_none());
+        assert!(store.column::<i32>().is_none());
+
+        let mut archetypal_store = Store::<u32>::with_archetypes();
+        archetypal_store.insert(0, 1.0f32);
+        assert!(archetypal_store.column::<f32>().is_none());
+        assert!(archetypal_store.column_mut::<f32>().is_none());
+    }
+
+    #[test]
+    fn zip_columns() {
+        #[derive(Debug, PartialEq)]
+        struct Position(f32);
+        #[derive(Debug, PartialEq)]
+        struct Velocity(f32);
+
+        let mut store = Store::<u32>::default();
+        store.register_storage_type_for::<Position>(StorageType::DenseVecMap);
+        store.register_storage_type_for::<Velocity>(StorageType::DenseVecMap);
+        store.register_storage_type_for::<u8>(StorageType::DenseVecMap);
+        for key in [4, 1, 9] {
+            store.insert(key, Position(key as f32));
+            store.insert(key, Velocity(1.0));
+            store.insert(key, key as u8);
+        }
+
+        {
+            let mut positions = store.column_mut::<Position>().unwrap();
+            let velocities = store.column::<Velocity>().unwrap();
+            let ids = store.column::<u8>().unwrap();
+            let zipped = (&mut positions, &velocities, &ids).zip_columns().unwrap();
+            assert_eq!(zipped.size_hint(), (3, Some(3)));
+            for (key, position, velocity, id) in zipped {
+                assert_eq!(key, *id as u32);
+                position.0 += velocity.0;
+            }
+        }
+
+        let positions = store.column::<Position>().unwrap();
+        assert_eq!(
+            positions.values(),
+            &[Position(5.0), Position(2.0), Position(10.0)]
+        );
+        drop(positions);
+
+        store.remove::<Velocity>(&4);
+        store.insert(4, Velocity(1.0));
+        let positions = store.column::<Position>().unwrap();
+        let velocities = store.column::<Velocity>().unwrap();
+        assert!((&positions, &velocities).zip_columns().is_none());
+    }
+}
@@ -0,0 +1,77 @@
+use crate::{
+    DynStorage, KeyRange, KeySet, KeySetOf, StorageTrait, StorageType, StoreBacking,
+    StoreBackingRefCell, StoreKey, TypedData,
+};
+use std::{any::Any, cell::Ref, fmt::Debug};
+
+/// Storage for a single value type, with its container chosen at runtime
+pub type TypedStoreBacking<Key, Value> = StoreBackingRefCell<DynStorage<Key, Value>>;
+
+/// Per-type vtable over a TypedStoreBacking, allowing Store to drive its storage without knowing the value type
+pub trait DynStoreBacking<Key>: Debug
+where
+    Key: StoreKey,
+{
+    fn as_any(&self) -> &dyn Any;
+    fn storage_type(&self) -> StorageType;
+    fn get_untyped(&self, key: &Key) -> Option<Ref<'_, dyn TypedData>>;
+    fn remove_untyped(&self, key: &Key) -> bool;
+    fn clear(&self);
+    fn contains(&self, key: &Key) -> bool;
+    fn keys(&self) -> KeySetOf<Key>;
+    fn dense_keys(&self) -> Option<Vec<Key>>;
+    fn range_keys(&self, range: &KeyRange<Key>) -> Vec<Key>;
+    fn migrate(&self, storage_type: StorageType) -> StoreBacking<Key>;
+}
+
+impl<Key, Value> DynStoreBacking<Key> for TypedStoreBacking<Key, Value>
+where
+    Key: StoreKey + 'static,
+    Value: Debug + 'static,
+{
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn storage_type(&self) -> StorageType {
+        self.values.borrow().storage_type()
+    }
+
+    fn get_untyped(&self, key: &Key) -> Option<Ref<'_, dyn TypedData>> {
+        let value = StoreBackingRefCell::get(self, key)?;
+        Some(Ref::map(value, |value| value as &dyn TypedData))
+    }
+
+    fn remove_untyped(&self, key: &Key) -> bool {
+        StoreBackingRefCell::remove_key(self, key)
+    }
+
+    fn clear(&self) {
+        StoreBackingRefCell::clear(self)
+    }
+
+    fn contains(&self, key: &Key) -> bool {
+        StoreBackingRefCell::contains(self, key)
+    }
+
+    fn keys(&self) -> KeySetOf<Key> {
+        StoreBackingRefCell::keys(self)
+    }
+
+    fn dense_keys(&self) -> Option<Vec<Key>> {
+        self.values.borrow().dense_keys().map(<[Key]>::to_vec)
+    }
+
+    fn range_keys(&self, range: &KeyRange<Key>) -> Vec<Key> {
+        self.values
+            .borrow()
+            .range_keys(range)
+            .unwrap_or_else(|| self.keys.borrow().range(range))
+    }
+
+    fn migrate(&self, storage_type: StorageType) -> StoreBacking<Key> {
+        let new_backing = StoreBacking::new::<Value>(storage_type);
+        new_backing.drain_from::<Value>(self);
+        new_backing
+    }
+}
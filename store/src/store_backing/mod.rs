@@ -1,99 +1,169 @@
+mod dyn_backing;
 mod ref_cell;
 
+pub use dyn_backing::*;
 pub use ref_cell::*;
 
-use hibitset::{BitIter, BitSet};
-
-use crate::{BTreeMap, HashMap, SparseVecMap, StoreKey, TypedData};
+use crate::{
+    DynStorage, KeyRange, KeySet, KeySetOf, StorageTrait, StorageType, StoreKey, TypedData,
+};
 use std::{cell::Ref, cell::RefMut, fmt::Debug};
 
-#[derive(Debug)]
-#[allow(clippy::clippy::enum_variant_names)]
-pub enum StoreBacking<Key>
+/// Type-erased storage for a single value type.
+/// Values are held unboxed in a TypedStoreBacking, with untyped operations dispatched through its DynStoreBacking vtable.
+/// Typed access downcasts once and then matches on the DynStorage container, rather than making a second virtual call.
+pub struct StoreBacking<Key>
+where
+    Key: StoreKey + 'static,
+{
+    backing: Box<dyn DynStoreBacking<Key>>,
+}
+
+impl<Key> Debug for StoreBacking<Key>
 where
     Key: StoreKey + 'static,
 {
-    BTreeMap(StoreBackingRefCell<BTreeMap<Key, TypedData>>),
-    HashMap(StoreBackingRefCell<HashMap<Key, TypedData>>),
-    SparseVecMap(StoreBackingRefCell<SparseVecMap<Key, TypedData>>),
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple(&format!("{:?}", self.storage_type()))
+            .field(&self.backing)
+            .finish()
+    }
 }
 
 impl<Key> StoreBacking<Key>
 where
-    Key: StoreKey,
+    Key: StoreKey + 'static,
 {
-    pub fn get(&self, key: &Key) -> Option<Ref<TypedData>> {
-        match self {
-            StoreBacking::BTreeMap(backing) => backing.get(key),
-            StoreBacking::HashMap(backing) => backing.get(key),
-            StoreBacking::SparseVecMap(backing) => backing.get(key),
+    pub fn new<T>(storage_type: StorageType) -> Self
+    where
+        T: Debug + 'static,
+    {
+        let storage = DynStorage::<Key, T>::new(storage_type);
+
+        StoreBacking {
+            backing: Box::new(TypedStoreBacking::new(storage)),
         }
     }
 
-    pub fn get_mut(&self, key: &Key) -> Option<RefMut<TypedData>> {
-        match self {
-            StoreBacking::BTreeMap(backing) => backing.get_mut(key),
-            StoreBacking::HashMap(backing) => backing.get_mut(key),
-            StoreBacking::SparseVecMap(backing) => backing.get_mut(key),
+    pub fn with_storage<T, S>(storage: S) -> Self
+    where
+        T: Debug + 'static,
+        S: StorageTrait<Key = Key, Value = T> + 'static,
+    {
+        let storage = DynStorage::Custom(Box::new(storage));
+        StoreBacking {
+            backing: Box::new(TypedStoreBacking::new(storage)),
         }
     }
 
-    pub fn insert(&self, key: Key, value: TypedData) {
-        match self {
-            StoreBacking::BTreeMap(backing) => backing.insert(key, value),
-            StoreBacking::HashMap(backing) => backing.insert(key, value),
-            StoreBacking::SparseVecMap(backing) => {
-                let u32_key: u32 = key.into();
-                if !backing.keys.borrow().contains(u32_key) {
-                    backing.keys.borrow_mut().add(u32_key);
-                    backing.values.borrow_mut().insert(key, value, false);
-                }
-            }
-        }
+    pub fn storage_type(&self) -> StorageType {
+        self.backing.storage_type()
     }
 
-    pub fn remove(&self, key: &Key) {
-        match self {
-            StoreBacking::BTreeMap(backing) => backing.remove(key),
-            StoreBacking::HashMap(backing) => backing.remove(key),
-            StoreBacking::SparseVecMap(backing) => {
-                let u32_key: u32 = (*key).into();
-                if backing.keys.borrow().contains(u32_key) {
-                    backing.remove(key)
-                }
-            }
+    /// Access the typed storage underneath, or None if it holds a different value type
+    pub fn downcast<T>(&self) -> Option<&TypedStoreBacking<Key, T>>
+    where
+        T: 'static,
+    {
+        // Call through the trait explicitly; the Box is itself TypedData and would shadow as_any
+        DynStoreBacking::as_any(&*self.backing).downcast_ref::<TypedStoreBacking<Key, T>>()
+    }
+
+    fn typed<T>(&self) -> &TypedStoreBacking<Key, T>
+    where
+        T: 'static,
+    {
+        self.downcast::<T>().unwrap_or_else(|| {
+            panic!(
+                "StoreBacking does not hold {} values",
+                std::any::type_name::<T>()
+            )
+        })
+    }
+
+    /// Move every key and value into a new backing of the given type
+    pub fn migrate(self, storage_type: StorageType) -> Self {
+        self.backing.migrate(storage_type)
+    }
+
+    /// Move every key and value into the provided backing
+    pub fn migrate_into<T>(self, new_backing: Self) -> Self
+    where
+        T: 'static,
+    {
+        new_backing.drain_from::<T>(self.typed::<T>());
+        new_backing
+    }
+
+    fn drain_from<T>(&self, old_backing: &TypedStoreBacking<Key, T>)
+    where
+        T: 'static,
+    {
+        let new_backing = self.typed::<T>();
+        for key in old_backing.keys().into_keys() {
+            new_backing.insert(key, old_backing.remove(&key).unwrap());
         }
     }
 
+    pub fn get<T>(&self, key: &Key) -> Option<Ref<'_, T>>
+    where
+        T: 'static,
+    {
+        self.typed::<T>().get(key)
+    }
+
+    pub fn get_mut<T>(&self, key: &Key) -> Option<RefMut<'_, T>>
+    where
+        T: 'static,
+    {
+        self.typed::<T>().get_mut(key)
+    }
+
+    pub fn insert<T>(&self, key: Key, value: T) -> Option<T>
+    where
+        T: 'static,
+    {
+        self.typed::<T>().insert(key, value)
+    }
+
+    pub fn remove<T>(&self, key: &Key) -> Option<T>
+    where
+        T: 'static,
+    {
+        self.typed::<T>().remove(key)
+    }
+
+    pub fn get_untyped(&self, key: &Key) -> Option<Ref<'_, dyn TypedData>> {
+        self.backing.get_untyped(key)
+    }
+
+    pub fn remove_untyped(&self, key: &Key) -> bool {
+        self.backing.remove_untyped(key)
+    }
+
     pub fn clear(&self) {
-        match self {
-            StoreBacking::BTreeMap(backing) => backing.clear(),
-            StoreBacking::HashMap(backing) => backing.clear(),
-            StoreBacking::SparseVecMap(backing) => backing.clear(),
-        }
+        self.backing.clear()
     }
 
     pub fn contains(&self, key: &Key) -> bool {
-        match self {
-            StoreBacking::BTreeMap(backing) => backing.contains(key),
-            StoreBacking::HashMap(backing) => backing.contains(key),
-            StoreBacking::SparseVecMap(backing) => backing.contains(key),
-        }
+        self.backing.contains(key)
     }
 
-    pub fn keys(&self) -> BitSet {
-        match self {
-            StoreBacking::BTreeMap(backing) => backing.keys(),
-            StoreBacking::HashMap(backing) => backing.keys(),
-            StoreBacking::SparseVecMap(backing) => backing.keys(),
-        }
+    pub fn keys(&self) -> KeySetOf<Key> {
+        self.backing.keys()
     }
 
-    pub fn iter_keys(&self) -> BitIter<BitSet> {
-        match self {
-            StoreBacking::BTreeMap(backing) => backing.keys.borrow().clone().into_iter(),
-            StoreBacking::HashMap(backing) => backing.keys.borrow().clone().into_iter(),
-            StoreBacking::SparseVecMap(backing) => backing.keys.borrow().clone().into_iter(),
-        }
+    /// Keys in packed order, if the underlying storage keeps them contiguous
+    pub fn dense_keys(&self) -> Option<Vec<Key>> {
+        self.backing.dense_keys()
+    }
+
+    /// Keys within a range in ascending order, walked from the underlying storage if it keeps them sorted
+    pub fn range_keys(&self, range: &KeyRange<Key>) -> Vec<Key> {
+        self.backing.range_keys(range)
+    }
+
+    pub fn iter_keys(&self) -> <KeySetOf<Key> as KeySet<Key>>::IntoIter {
+        self.backing.keys().into_keys()
     }
 }
@@ -1,27 +1,34 @@
-use hibitset::BitSet;
+use crate::{KeySet, KeySetOf, StorageTrait};
+use std::{cell::Cell, cell::Ref, cell::RefCell, cell::RefMut, fmt::Debug};
 
-use crate::StorageTrait;
-use std::{cell::Ref, cell::RefCell, cell::RefMut, fmt::Debug};
-
-#[derive(Default, Clone, Eq, PartialEq)]
+#[derive(Default, Clone, PartialEq)]
 pub struct StoreBackingRefCell<Storage>
 where
     Storage: StorageTrait,
 {
-    pub keys: RefCell<BitSet>,
+    pub keys: RefCell<KeySetOf<Storage::Key>>,
     pub values: RefCell<Storage>,
+    /// Number of keys, kept alongside the key set as counting a BitSet walks all of it
+    pub len: Cell<usize>,
 }
 
 impl<Storage> StoreBackingRefCell<Storage>
 where
     Storage: StorageTrait,
 {
+    pub fn new(storage: Storage) -> Self {
+        StoreBackingRefCell {
+            keys: Default::default(),
+            values: RefCell::new(storage),
+            len: Cell::new(0),
+        }
+    }
+
     pub fn get(
         &self,
         key: &<Storage as StorageTrait>::Key,
-    ) -> Option<Ref<<Storage as StorageTrait>::Value>> {
-        let u32_key: u32 = (*key).into();
-        if self.keys.borrow().contains(u32_key) {
+    ) -> Option<Ref<'_, <Storage as StorageTrait>::Value>> {
+        if self.keys.borrow().contains(key) {
             Some(Ref::map(self.values.borrow(), |values| {
                 values.get(key).unwrap()
             }))
@@ -33,9 +40,8 @@ where
     pub fn get_mut(
         &self,
         key: &<Storage as StorageTrait>::Key,
-    ) -> Option<RefMut<<Storage as StorageTrait>::Value>> {
-        let u32_key: u32 = (*key).into();
-        if self.keys.borrow_mut().contains(u32_key) {
+    ) -> Option<RefMut<'_, <Storage as StorageTrait>::Value>> {
+        if self.keys.borrow().contains(key) {
             Some(RefMut::map(self.values.borrow_mut(), |values| {
                 values.get_mut(key).unwrap()
             }))
@@ -48,29 +54,60 @@ where
         &self,
         key: <Storage as StorageTrait>::Key,
         value: <Storage as StorageTrait>::Value,
-    ) {
-        let u32_key: u32 = key.into();
-        self.keys.borrow_mut().add(u32_key);
-        self.values.borrow_mut().insert(key, value);
+    ) -> Option<<Storage as StorageTrait>::Value> {
+        if self.keys.borrow().contains(&key) {
+            let mut values = self.values.borrow_mut();
+            Some(std::mem::replace(values.get_mut(&key).unwrap(), value))
+        } else {
+            self.keys.borrow_mut().insert(&key);
+            self.values.borrow_mut().insert(key, value);
+            self.len.set(self.len.get() + 1);
+            None
+        }
+    }
+
+    pub fn remove(
+        &self,
+        key: &<Storage as StorageTrait>::Key,
+    ) -> Option<<Storage as StorageTrait>::Value> {
+        if self.keys.borrow_mut().remove(key) {
+            self.len.set(self.len.get() - 1);
+            self.values.borrow_mut().remove(key)
+        } else {
+            None
+        }
     }
 
-    pub fn remove(&self, key: &<Storage as StorageTrait>::Key) {
-        let u32_key: u32 = (*key).into();
-        self.keys.borrow_mut().remove(u32_key);
-        self.values.borrow_mut().remove(&key);
+    /// Remove a key and drop its value, returning whether the key was present
+    pub fn remove_key(&self, key: &<Storage as StorageTrait>::Key) -> bool {
+        if self.keys.borrow_mut().remove(key) {
+            self.len.set(self.len.get() - 1);
+            self.values.borrow_mut().remove(key);
+            true
+        } else {
+            false
+        }
     }
 
     pub fn clear(&self) {
         self.keys.borrow_mut().clear();
         self.values.borrow_mut().clear();
+        self.len.set(0);
+    }
+
+    pub fn len(&self) -> usize {
+        self.len.get()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len.get() == 0
     }
 
     pub fn contains(&self, key: &<Storage as StorageTrait>::Key) -> bool {
-        let u32_key: u32 = (*key).into();
-        self.keys.borrow().contains(u32_key)
+        self.keys.borrow().contains(key)
     }
 
-    pub fn keys(&self) -> BitSet {
+    pub fn keys(&self) -> KeySetOf<Storage::Key> {
         self.keys.borrow().clone()
     }
 }
@@ -82,12 +119,10 @@ where
 {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_map()
-            .entries(self.keys.borrow().clone().into_iter().map(|key| {
+            .entries(self.keys.borrow().iter().map(|key| {
                 (
                     key,
-                    Ref::map(self.values.borrow(), |values| {
-                        values.get(&key.into()).unwrap()
-                    }),
+                    Ref::map(self.values.borrow(), |values| values.get(&key).unwrap()),
                 )
             }))
             .finish()
@@ -1,5 +1,207 @@
-use std::{fmt::Debug, hash::Hash};
+use std::{
+    convert::TryFrom,
+    fmt::Debug,
+    hash::Hash,
+    ops::{Bound, RangeBounds},
+};
 
-pub trait StoreKey: Debug + Copy + Ord + Hash + From<u32> + Into<u32> {}
+use hibitset::BitSet;
 
-impl<T> StoreKey for T where T: Debug + Copy + Ord + Hash + From<u32> + Into<u32> {}
+use crate::{KeySet, RoaringSet};
+
+/// Key addressing values in a Store, positioned by a u64 index
+pub trait StoreKey: Debug + Copy + Ord + Hash + 'static {
+    /// Set used to track which keys hold values.
+    /// Key types whose indices fit hibitset's range use BitSet, while wide or sparse key spaces use RoaringSet.
+    type KeySet: KeySet<Self>;
+
+    /// Whether handles encode a generation, so that released indices must track their live generation
+    const GENERATIONAL: bool = false;
+
+    /// Index of the key, ignoring any generation
+    fn to_index(&self) -> u64;
+
+    fn from_index(index: u64) -> Self;
+
+    /// Build the handle for an index at the given generation.
+    /// Keys that do not track generations ignore it, and are never considered stale.
+    fn from_parts(index: u64, _generation: u32) -> Self {
+        Self::from_index(index)
+    }
+}
+
+impl StoreKey for u32 {
+    type KeySet = BitSet;
+
+    fn to_index(&self) -> u64 {
+        u64::from(*self)
+    }
+
+    fn from_index(index: u64) -> Self {
+        u32::try_from(index).expect("Key index exceeds the u32 key range")
+    }
+}
+
+impl StoreKey for u64 {
+    type KeySet = RoaringSet;
+
+    fn to_index(&self) -> u64 {
+        *self
+    }
+
+    fn from_index(index: u64) -> Self {
+        index
+    }
+}
+
+/// Set type tracking the membership of a key type
+pub type KeySetOf<Key> = <Key as StoreKey>::KeySet;
+
+/// Whether a key type's index space extends past u32, beyond what index-addressed storage can hold
+pub(crate) fn is_wide<Key>() -> bool
+where
+    Key: StoreKey,
+{
+    <Key::KeySet as KeySet<Key>>::MAX_INDEX > u64::from(u32::MAX)
+}
+
+/// Index of a key within u32-indexed storage
+pub(crate) fn u32_index<Key>(key: &Key) -> u32
+where
+    Key: StoreKey,
+{
+    let index = key.to_index();
+    u32::try_from(index).unwrap_or_else(|_| {
+        panic!(
+            "Key index {} exceeds the u32 range of index-addressed storage",
+            index
+        )
+    })
+}
+
+/// Panic unless a key's index fits the key set of its type, e.g. the 2^24 indices of a BitSet
+pub(crate) fn assert_key_in_range<Key>(key: &Key)
+where
+    Key: StoreKey,
+{
+    let max_index = <Key::KeySet as KeySet<Key>>::MAX_INDEX;
+    assert!(
+        key.to_index() <= max_index,
+        "Key index {} exceeds the largest index {} that {} keys can hold",
+        key.to_index(),
+        max_index,
+        std::any::type_name::<Key>()
+    );
+}
+
+/// Owned start and end bounds of a range of keys
+pub type KeyRange<Key> = (Bound<Key>, Bound<Key>);
+
+pub(crate) fn key_range<Key, R>(range: &R) -> KeyRange<Key>
+where
+    Key: StoreKey,
+    R: RangeBounds<Key>,
+{
+    // Compare by index alone, as storages hold keys without their generation
+    let bound = |bound: Bound<&Key>| bound.map(|key| Key::from_index(key.to_index()));
+    (bound(range.start_bound()), bound(range.end_bound()))
+}
+
+/// Whether a range can hold no key, including inverted ranges such as 5..2
+pub(crate) fn key_range_is_empty<Key>(range: &KeyRange<Key>) -> bool
+where
+    Key: StoreKey,
+{
+    match (range.0, range.1) {
+        (Bound::Included(start), Bound::Included(end)) => start.to_index() > end.to_index(),
+        (Bound::Included(start), Bound::Excluded(end))
+        | (Bound::Excluded(start), Bound::Included(end))
+        | (Bound::Excluded(start), Bound::Excluded(end)) => start.to_index() >= end.to_index(),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{GenerationalKey, Ref, Store, StoreKey, StoreQuery};
+
+    #[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Ord, PartialOrd, Hash, StoreKey)]
+    #[store_key(prefix = "e")]
+    struct EntityId(u32);
+
+    #[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Ord, PartialOrd, Hash, StoreKey)]
+    struct AssetId(u64);
+
+    #[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Ord, PartialOrd, Hash, StoreKey)]
+    struct Handle(GenerationalKey);
+
+    #[test]
+    #[should_panic(expected = "exceeds the u32 key range")]
+    fn u32_from_index_out_of_range() {
+        u32::from_index(u64::from(u32::MAX) + 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "exceeds the largest index 16777215")]
+    fn bit_set_key_out_of_range() {
+        let mut store = Store::<u32>::default();
+        store.insert(1 << 24, "beyond");
+    }
+
+    #[test]
+    fn bit_set_key_range() {
+        let mut store = Store::<u32>::default();
+        let last = (1 << 24) - 1;
+        store.insert(last, "last");
+        store.insert(3, "three");
+        assert_eq!(store.keys_all().len(), 2);
+        assert_eq!(
+            store.keys_all().into_iter().collect::<Vec<_>>(),
+            vec![3, last]
+        );
+        assert_eq!(StoreQuery::<(u32, Ref<&str>)>::iter(&store).count(), 2);
+
+        // Lookups beyond the range find nothing rather than panicking
+        assert!(store.get::<&str>(&(1 << 24)).is_none());
+        assert!(store.get::<&str>(&20_000_000).is_none());
+        assert!(!store.contains_key(&20_000_000));
+        store.remove::<&str>(&20_000_000);
+        store.remove_key(&20_000_000);
+
+        let mut handles = Store::<GenerationalKey>::default();
+        let handle = handles.spawn(("handle",));
+        let keys = handles.keys::<&str>();
+        assert_eq!(keys.len(), 1);
+        assert!(keys.contains(&handle));
+        assert_eq!(
+            keys.iter().map(|key| key.index()).collect::<Vec<_>>(),
+            vec![0]
+        );
+    }
+
+    #[test]
+    fn derive_store_key() {
+        let mut store = Store::<EntityId>::default();
+        store.insert(EntityId(2), "two");
+        store.insert(EntityId::from(5), "five");
+        assert_eq!(u32::from(EntityId(5)), 5);
+        assert_eq!(EntityId(5).to_string(), "e5");
+
+        let keys: Vec<String> = StoreQuery::<(EntityId, Ref<&str>)>::iter(&store)
+            .map(|(key, _)| key.to_string())
+            .collect();
+        println!("{:?}", keys);
+        assert_eq!(keys, vec!["e2", "e5"]);
+
+        let mut assets = Store::<AssetId>::default();
+        assets.insert(AssetId(1 << 40), "texture");
+        assert_eq!(*assets.get::<&str>(&AssetId(1 << 40)).unwrap(), "texture");
+
+        let mut handles = Store::<Handle>::default();
+        let handle = handles.spawn(("handle",));
+        handles.remove_key(&handle);
+        let reused = handles.spawn(("reused",));
+        assert_eq!(reused.to_index(), handle.to_index());
+        assert!(!handles.is_current(&handle));
+    }
+}
@@ -3,11 +3,11 @@ use std::{
     fmt::Debug,
     hash::Hash,
     marker::PhantomData,
+    ops::RangeBounds,
 };
 
-use crate::{Store, TypeKey};
+use crate::{key_range, KeySet, KeySetOf, StorageAccess, Store, StoreKey, TypeKey};
 
-use hibitset::{BitIter, BitSet};
 use store_macros::impl_store_fields_iterator;
 
 // Core Types
@@ -31,24 +31,136 @@ impl<T> Debug for NoField<T> {
 
 pub trait StoreQuery<'a, Signature>
 where
-    Self::Key: Debug + Copy + Ord + From<u32> + Into<u32> + Hash,
+    Self::Key: StoreKey,
 {
     type Key;
 
+    /// Fetch the fields of a key, panicking if it is stale, disabled or missing a required field
     fn get(&'a self, key: &Self::Key) -> Signature;
-    fn iter(&'a self) -> StoreIterator<Self::Key, Signature>;
-    fn iter_keys(&'a self, keys: &'a [Self::Key]) -> StoreIterator<Self::Key, Signature>;
+    fn iter(&'a self) -> StoreIterator<'a, Self::Key, Signature>;
+    fn iter_keys(&'a self, keys: &'a [Self::Key]) -> StoreIterator<'a, Self::Key, Signature>;
+    fn iter_range<R>(&'a self, range: R) -> StoreIterator<'a, Self::Key, Signature>
+    where
+        R: RangeBounds<Self::Key>;
 }
 
+/// Iterator over the fields of matching keys.
+/// Storages are borrowed per item, so the store can be accessed between items as long as the previous item is dropped.
 pub struct StoreIterator<'a, Key, Signature>
 where
-    Key: Debug + Copy + Eq + Ord + Hash + From<u32> + Into<u32> + 'static,
+    Key: StoreKey + 'static,
 {
     store: &'a Store<Key>,
-    keys: BitIter<BitSet>,
+    keys: StoreIteratorKeys<Key>,
     _phantom_data: PhantomData<Signature>,
 }
 
+/// Packed values of a single type within a query segment, borrowed to read one row
+pub struct QueryColumn<'a, T>(Ref<'a, [T]>);
+
+impl<'a, T> QueryColumn<'a, T> {
+    pub(crate) fn new(values: Ref<'a, [T]>) -> Self {
+        QueryColumn(values)
+    }
+
+    fn get(self, row: usize) -> Ref<'a, T> {
+        Ref::map(self.0, |values| &values[row])
+    }
+}
+
+/// Packed values of a single type within a query segment, mutably borrowed to read one row
+pub struct QueryColumnMut<'a, T>(RefMut<'a, [T]>);
+
+impl<'a, T> QueryColumnMut<'a, T> {
+    pub(crate) fn new(values: RefMut<'a, [T]>) -> Self {
+        QueryColumnMut(values)
+    }
+
+    fn get_mut(self, row: usize) -> RefMut<'a, T> {
+        RefMut::map(self.0, |values| &mut values[row])
+    }
+}
+
+/// Storage a query row lives in, whose columns are read by row rather than by key
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) enum QuerySegment {
+    /// The packed storage of the type driving the query
+    Dense(TypeKey),
+    /// An archetype table, holding a column for every type its rows hold
+    Table(usize),
+}
+
+impl QuerySegment {
+    /// Whether every type a row holds is borrowed as a column, so a missing column means the row lacks the type
+    pub(crate) fn holds_all_columns(&self) -> bool {
+        matches!(self, QuerySegment::Table(_))
+    }
+}
+
+/// Keys visited by a StoreIterator: ascending from a key set, a precomputed list, or the rows of a dense storage or archetype tables
+pub(crate) enum StoreIteratorKeys<Key>
+where
+    Key: StoreKey,
+{
+    Sparse(<KeySetOf<Key> as KeySet<Key>>::IntoIter),
+    Listed(std::vec::IntoIter<Key>),
+    Dense(TypeKey, std::vec::IntoIter<(Key, usize)>),
+    Tables(std::vec::IntoIter<(Key, usize, usize)>),
+}
+
+impl<Key> StoreIteratorKeys<Key>
+where
+    Key: StoreKey,
+{
+    /// Walk `keys` in the packed order of the driving type's `dense_keys` if provided, yielding each key's row
+    pub(crate) fn new(keys: KeySetOf<Key>, dense_keys: Option<(TypeKey, Vec<Key>)>) -> Self {
+        match dense_keys {
+            Some((type_key, dense_keys)) => StoreIteratorKeys::Dense(
+                type_key,
+                dense_keys
+                    .into_iter()
+                    .enumerate()
+                    .filter(|(_, key)| keys.contains(key))
+                    .map(|(row, key)| (key, row))
+                    .collect::<Vec<(Key, usize)>>()
+                    .into_iter(),
+            ),
+            None => StoreIteratorKeys::Sparse(keys.into_keys()),
+        }
+    }
+
+    /// Walk archetype rows table by table, yielding each key with its table and row
+    pub(crate) fn from_rows(rows: Vec<(Key, usize, usize)>) -> Self {
+        StoreIteratorKeys::Tables(rows.into_iter())
+    }
+
+    /// Walk a precomputed list of keys in the given order
+    pub(crate) fn from_keys(keys: Vec<Key>) -> Self {
+        StoreIteratorKeys::Listed(keys.into_iter())
+    }
+}
+
+impl<Key> Iterator for StoreIteratorKeys<Key>
+where
+    Key: StoreKey,
+{
+    /// A key, with its segment and row if its values can be read from borrowed columns
+    type Item = (Key, Option<(QuerySegment, usize)>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            StoreIteratorKeys::Sparse(keys) => keys.next().map(|key| (key, None)),
+            StoreIteratorKeys::Listed(keys) => keys.next().map(|key| (key, None)),
+            StoreIteratorKeys::Dense(type_key, keys) => keys
+                .next()
+                .map(|(key, row)| (key, Some((QuerySegment::Dense(*type_key), row)))),
+            StoreIteratorKeys::Tables(rows) => rows
+                .next()
+                .map(|(key, table, row)| (key, Some((QuerySegment::Table(table), row)))),
+        }
+    }
+}
+
 impl_store_fields_iterator!(1..6);
 
 // Tests
@@ -59,12 +171,13 @@ mod tests {
     struct SomeData<T>(pub T);
 
     use super::*;
+    use crate::StorageType;
 
     #[test]
     fn debug() {
         let mut store = Store::<u32>::default();
 
-        //store.register_storage_type_for::<SomeData<String>>(StorageType::BTreeMap);
+        store.register_storage_type_for::<SomeData<String>>(StorageType::BTreeMap);
 
         store.insert(0, false);
         store.insert(1, true);
@@ -93,7 +206,7 @@ mod tests {
     fn get() {
         let mut store = Store::<u32>::default();
 
-        //store.register_storage_type_for::<SomeData<String>>(StorageType::BTreeMap);
+        store.register_storage_type_for::<SomeData<String>>(StorageType::BTreeMap);
 
         store.insert(0, false);
         store.insert(1, true);
@@ -205,4 +318,207 @@ mod tests {
         StoreQuery::<(u32, NoField<i32>)>::iter(&store)
             .for_each(|result| println!("Result: {:?}", result));
     }
+
+    #[test]
+    fn unit_storage() {
+        #[derive(Debug, Copy, Clone, Eq, PartialEq)]
+        struct Marker;
+
+        let mut store = Store::<u32>::default();
+
+        store.insert(0, 0);
+        store.insert(1, 1);
+        store.insert(2, 2);
+
+        store.insert(0, Marker);
+        store.insert(2, Marker);
+
+        println!("\nStore: {:#?}\n", store);
+
+        let marked: Vec<u32> = StoreQuery::<(u32, Ref<i32>, Ref<Marker>)>::iter(&store)
+            .map(|(key, _, marker)| {
+                assert_eq!(*marker, Marker);
+                key
+            })
+            .collect();
+        assert_eq!(marked, vec![0, 2]);
+
+        let unmarked: Vec<u32> = StoreQuery::<(u32, NoField<Marker>, Ref<i32>)>::iter(&store)
+            .map(|(key, _, _)| key)
+            .collect();
+        assert_eq!(unmarked, vec![1]);
+
+        store.remove::<Marker>(&0);
+        assert!(store.get::<Marker>(&0).is_none());
+        assert!(store.get::<Marker>(&2).is_some());
+    }
+
+    #[test]
+    fn archetypes() {
+        let mut store = Store::<u32>::with_archetypes();
+
+        store.insert(0, false);
+        store.insert(1, true);
+        store.insert(2, true);
+        store.insert(3, false);
+
+        store.insert(1, "World");
+        store.insert(3, "Farewell");
+
+        store.insert(1, 2);
+        store.insert(2, 4);
+
+        store.insert(0, SomeData("Ahoy".to_string()));
+        store.insert(3, SomeData("Landlubbers".to_string()));
+
+        let results: Vec<(u32, bool, Option<i32>)> =
+            StoreQuery::<(u32, NoField<SomeData<String>>, Ref<bool>, Option<Ref<i32>>)>::iter(
+                &store,
+            )
+            .map(|(key, _, flag, int)| (key, *flag, int.map(|int| *int)))
+            .collect();
+        assert_eq!(results, vec![(1, true, Some(2)), (2, true, Some(4))]);
+
+        let mut keys: Vec<u32> = StoreQuery::<(u32, Ref<bool>, RefMut<&'static str>)>::iter(&store)
+            .map(|(key, _, mut string)| {
+                *string = "Edited";
+                key
+            })
+            .collect();
+        keys.sort();
+        assert_eq!(keys, vec![1, 3]);
+        assert_eq!(*store.get::<&'static str>(&3).unwrap(), "Edited");
+
+        // Table columns are borrowed per item, so the store stays usable between items
+        for (key, _, int) in StoreQuery::<(u32, Ref<bool>, RefMut<i32>)>::iter(&store) {
+            let incremented = *int + 1;
+            drop(int);
+            *store.get_mut::<i32>(&key).unwrap() = incremented;
+        }
+        assert_eq!(*store.get::<i32>(&1).unwrap(), 3);
+        assert_eq!(*store.get::<i32>(&2).unwrap(), 5);
+
+        // Tables without an optional type yield None
+        let mut ints: Vec<(u32, Option<i32>)> =
+            StoreQuery::<(u32, Ref<bool>, Option<RefMut<i32>>)>::iter(&store)
+                .map(|(key, _, int)| {
+                    (
+                        key,
+                        int.map(|mut int| {
+                            *int += 10;
+                            *int
+                        }),
+                    )
+                })
+                .collect();
+        ints.sort();
+        assert_eq!(
+            ints,
+            vec![(0, None), (1, Some(13)), (2, Some(15)), (3, None)]
+        );
+        assert_eq!(*store.get::<i32>(&2).unwrap(), 15);
+
+        let (key, flag, data) =
+            StoreQuery::<(u32, Ref<bool>, Option<Ref<SomeData<String>>>)>::get(&store, &0);
+        assert_eq!(
+            (key, *flag, data.map(|data| data.0.clone())),
+            (0, false, Some("Ahoy".to_string()))
+        );
+    }
+
+    #[test]
+    fn sparse_set_storage() {
+        let mut store = Store::<u32>::default();
+        store.register_storage_type_for::<i32>(StorageType::DenseVecMap);
+
+        store.insert(5, 50);
+        store.insert(1, 10);
+        store.insert(3, 30);
+        store.insert(7, 70);
+        store.remove::<i32>(&5);
+
+        store.insert(1, true);
+        store.insert(3, false);
+        store.insert(7, true);
+
+        // Iteration follows the DenseVecMap's packed order rather than ascending key order
+        let results: Vec<(u32, i32)> = StoreQuery::<(u32, Ref<i32>, Ref<bool>)>::iter(&store)
+            .map(|(key, int, _)| (key, *int))
+            .collect();
+        assert_eq!(results, vec![(7, 70), (1, 10), (3, 30)]);
+
+        let results: Vec<u32> = StoreQuery::<(u32, Ref<bool>, RefMut<i32>)>::iter(&store)
+            .filter(|(_, flag, _)| **flag)
+            .map(|(key, _, mut int)| {
+                *int += 1;
+                key
+            })
+            .collect();
+        assert_eq!(results, vec![7, 1]);
+        assert_eq!(*store.get::<i32>(&7).unwrap(), 71);
+
+        // Storages are borrowed per item, so the store stays usable between items
+        store.insert(9, 90);
+        store.insert(2, 20);
+        store.insert(2, true);
+        for (key, _, int) in StoreQuery::<(u32, Ref<bool>, RefMut<i32>)>::iter(&store) {
+            let doubled = *int * 2;
+            drop(int);
+            *store.get_mut::<i32>(&key).unwrap() = doubled;
+        }
+        let results: Vec<(u32, i32)> = StoreQuery::<(u32, Ref<i32>)>::iter(&store)
+            .map(|(key, int)| (key, *int))
+            .collect();
+        println!("{:?}", results);
+        assert_eq!(results, vec![(7, 142), (1, 22), (3, 60), (9, 90), (2, 40)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "already borrowed")]
+    fn iter_item_held() {
+        let mut store = Store::<u32>::default();
+        store.register_storage_type_for::<i32>(StorageType::DenseVecMap);
+        store.insert(1, 10);
+
+        // A live item keeps its storage borrowed
+        for (key, int) in StoreQuery::<(u32, Ref<i32>)>::iter(&store) {
+            *store.get_mut::<i32>(&key).unwrap() += *int;
+        }
+    }
+
+    #[test]
+    fn iter_range() {
+        let mut store = Store::<u32>::default();
+        store.register_storage_type_for::<String>(StorageType::BTreeMap);
+        store.register_storage_type_for::<i32>(StorageType::DenseVecMap);
+
+        for key in [12, 3, 8, 5, 20, 1] {
+            store.insert(key, key as i32);
+            if key % 4 != 0 {
+                store.insert(key, key.to_string());
+            }
+        }
+        store.insert(4, false);
+        store.insert(5, true);
+
+        // Ordered storage is walked over the range
+        let results: Vec<u32> =
+            StoreQuery::<(u32, Ref<String>, RefMut<i32>)>::iter_range(&store, 2..20)
+                .map(|(key, _, _)| key)
+                .collect();
+        assert_eq!(results, vec![3, 5]);
+
+        // Unordered storage is windowed, still in ascending key order
+        let results: Vec<u32> =
+            StoreQuery::<(u32, NoField<bool>, Ref<i32>)>::iter_range(&store, 3..=12)
+                .map(|(key, _, _)| key)
+                .collect();
+        assert_eq!(results, vec![3, 8, 12]);
+
+        let results: Vec<u32> =
+            StoreQuery::<(u32, Ref<i32>, Option<Ref<String>>)>::iter_range(&store, 8..)
+                .map(|(key, _, _)| key)
+                .collect();
+        assert_eq!(results, vec![8, 12, 20]);
+    }
 }
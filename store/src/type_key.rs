@@ -55,7 +55,7 @@ impl Eq for TypeKey {}
 
 impl PartialOrd for TypeKey {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        self.0.partial_cmp(&other.0)
+        Some(self.cmp(other))
     }
 }
 
@@ -1,58 +1,37 @@
-use std::{
-    any::Any,
-    fmt::{self, Debug},
-    ops::Deref,
-    ops::DerefMut,
-};
+use std::{any::Any, fmt::Debug};
 
-/// Introspective polymorphic storage for Any types
-pub struct TypedData {
-    data: Box<dyn Any>,
-    fmt: Box<dyn Fn(&TypedData, &mut fmt::Formatter) -> Result<(), fmt::Error>>,
+/// Introspective view over a stored value of any type.
+/// Implemented for every Debug type, so type-erased values can be debug printed and downcast.
+pub trait TypedData: Any + Debug {
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
 }
 
-impl TypedData {
-    pub fn new<T>(data: T) -> Self
-    where
-        T: Debug + Any,
-    {
-        TypedData {
-            data: Box::new(data),
-            fmt: Box::new(|type_data, f| type_data.data.downcast_ref::<T>().unwrap().fmt(f)),
-        }
+impl<T> TypedData for T
+where
+    T: Any + Debug,
+{
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
     }
+}
 
+impl dyn TypedData {
     pub fn downcast<T>(&self) -> Option<&T>
     where
         T: 'static,
     {
-        self.data.downcast_ref::<T>()
+        self.as_any().downcast_ref::<T>()
     }
 
     pub fn downcast_mut<T>(&mut self) -> Option<&mut T>
     where
         T: 'static,
     {
-        self.data.downcast_mut::<T>()
-    }
-}
-
-impl<'a> Debug for TypedData {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        (&self.fmt)(self, f)
-    }
-}
-
-impl Deref for TypedData {
-    type Target = Box<dyn Any>;
-
-    fn deref(&self) -> &Self::Target {
-        &self.data
-    }
-}
-
-impl DerefMut for TypedData {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.data
+        self.as_any_mut().downcast_mut::<T>()
     }
 }
@@ -3,12 +3,12 @@ use std::{
     time::Duration,
 };
 
-use store::{Store, StoreQuery};
+use store::{StorageType, Store, StoreQuery};
 
 fn main() {
     let mut store = Store::<u32>::default();
 
-    //store.register_storage_type_for::<String>(StorageType::BTreeMap);
+    store.register_storage_type_for::<String>(StorageType::BTreeMap);
 
     store.insert(0, false);
     store.insert(1, true);
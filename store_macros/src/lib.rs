@@ -2,6 +2,7 @@ extern crate proc_macro;
 
 mod store_fields;
 mod assemblage;
+mod store_key;
 mod tuple;
 
 use proc_macro::TokenStream;
@@ -25,3 +26,10 @@ pub fn impl_assemble_chain(input: TokenStream) -> TokenStream {
 pub fn impl_disassemble(input: TokenStream) -> TokenStream {
     assemblage::impl_disassemble(input)
 }
+
+/// Implement StoreKey for a single-field tuple struct by delegating to its field, along with From conversions to and from it.
+/// `#[store_key(prefix = "e")]` also implements Display as the prefix followed by the field.
+#[proc_macro_derive(StoreKey, attributes(store_key))]
+pub fn derive_store_key(input: TokenStream) -> TokenStream {
+    store_key::derive_store_key(input)
+}
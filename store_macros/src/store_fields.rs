@@ -143,6 +143,33 @@ pub fn impl_store_fields_inner(
     let (option_ref_storage_vars, sv) = sv.split_at(option_ref_idents.len());
     let (mut_ref_storage_vars, option_mut_ref_storage_vars) = sv.split_at(mut_ref_idents.len());
 
+    let column_vars: Vec<Ident> = (no_field_idents.len()..type_idents.len())
+        .map(|i| syn::Ident::new(&format!("c{}", i), Span::call_site()))
+        .collect();
+
+    let (ref_column_vars, cv) = column_vars.split_at(ref_idents.len());
+    let (option_ref_column_vars, cv) = cv.split_at(option_ref_idents.len());
+    let (mut_ref_column_vars, option_mut_ref_column_vars) = cv.split_at(mut_ref_idents.len());
+
+    // Signatures of NoField alone have no columns to borrow, so every value is looked up by key
+    let borrow_columns = if column_vars.is_empty() {
+        quote!()
+    } else {
+        quote!(
+            let (#(#column_vars,)*) = match slot {
+                Some((segment, _)) => (
+                    #(self.store.query_column::<#ref_idents>(segment),)*
+                    #(self.store.query_column::<#option_ref_idents>(segment),)*
+                    #(self.store.query_column_mut::<#mut_ref_idents>(segment),)*
+                    #(self.store.query_column_mut::<#option_mut_ref_idents>(segment),)*
+                ),
+                None => Default::default(),
+            };
+            let row = slot.map(|(_, row)| row);
+            let holds_all_columns = slot.is_some_and(|(segment, _)| segment.holds_all_columns());
+        )
+    };
+
     quote!(
         impl<'a, Key, #(#type_idents),*> StoreQuery<'a, (
             Key,
@@ -153,7 +180,7 @@ pub fn impl_store_fields_inner(
             #(Option<RefMut<'a, #option_mut_ref_idents>>,)*
         )> for Store<Key>
         where
-            Key: Debug + Default + Copy + Ord + Hash + From<u32> + Into<u32> + 'static,
+            Key: StoreKey + Default + 'static,
             #(
                 #type_idents: 'static,
             )*
@@ -168,6 +195,9 @@ pub fn impl_store_fields_inner(
                 #(RefMut<'a, #mut_ref_idents>,)*
                 #(Option<RefMut<'a, #option_mut_ref_idents>>,)*
             ) {
+                assert!(self.is_current(key), "Supplied key {:?} is stale", key);
+                assert!(!self.is_disabled(key), "Supplied key {:?} is disabled, use get_including_disabled to reach its values", key);
+
                 #(
                     assert!(!self.contains_type_key::<#no_field_idents>(key));
                 )*
@@ -196,23 +226,38 @@ pub fn impl_store_fields_inner(
                 #(RefMut<'a, #mut_ref_idents>,)*
                 #(Option<RefMut<'a, #option_mut_ref_idents>>,)*
             )> {
-                let mut keys = self.keys_all();
-
-                #(
-                    keys &= &(!self.keys::<#no_field_idents>());
-                )*
+                let keys = self.query_keys(
+                    &[#(TypeKey::of::<#ref_idents>(),)* #(TypeKey::of::<#mut_ref_idents>(),)*],
+                    &[#(TypeKey::of::<#no_field_idents>(),)*],
+                );
 
-                #(
-                    keys &= &(self.keys::<#ref_idents>());
-                )*
+                StoreIterator {
+                    store: self,
+                    keys,
+                    _phantom_data: PhantomData,
+                }
+            }
 
-                #(
-                    keys &= &(self.keys::<#mut_ref_idents>());
-                )*
+            fn iter_range<R>(&'a self, range: R) -> StoreIterator<Key, (
+                Key,
+                #(NoField<#no_field_idents>,)*
+                #(Ref<'a, #ref_idents>,)*
+                #(Option<Ref<'a, #option_ref_idents>>,)*
+                #(RefMut<'a, #mut_ref_idents>,)*
+                #(Option<RefMut<'a, #option_mut_ref_idents>>,)*
+            )>
+            where
+                R: RangeBounds<Key>,
+            {
+                let keys = self.query_range_keys(
+                    &[#(TypeKey::of::<#ref_idents>(),)* #(TypeKey::of::<#mut_ref_idents>(),)*],
+                    &[#(TypeKey::of::<#no_field_idents>(),)*],
+                    &key_range(&range),
+                );
 
                 StoreIterator {
                     store: self,
-                    keys: keys.into_iter(),
+                    keys,
                     _phantom_data: PhantomData,
                 }
             }
@@ -225,14 +270,14 @@ pub fn impl_store_fields_inner(
                 #(RefMut<'a, #mut_ref_idents>,)*
                 #(Option<RefMut<'a, #option_mut_ref_idents>>,)*
             )> {
-                let mut bit_set = BitSet::new();
-                for key in keys {
-                    bit_set.add((*key).into());
+                let mut key_set = KeySetOf::<Key>::default();
+                for key in keys.iter().filter(|key| self.is_current(key) && !self.is_disabled(key)) {
+                    key_set.insert(key);
                 }
 
                 StoreIterator {
                     store: self,
-                    keys: bit_set.into_iter(),
+                    keys: StoreIteratorKeys::new(key_set, None),
                     _phantom_data: PhantomData,
                 }
             }
@@ -247,7 +292,7 @@ pub fn impl_store_fields_inner(
             #(Option<RefMut<'a, #option_mut_ref_idents>>,)*
         )>
         where
-            Key: Debug + Default + Copy + Ord + Hash + From<u32> + Into<u32> + 'static,
+            Key: StoreKey + Default + 'static,
             #(
                 #type_idents: 'static,
             )*
@@ -262,26 +307,51 @@ pub fn impl_store_fields_inner(
             );
 
             fn next(&mut self) -> Option<Self::Item> {
-                if let Some(key) = self.keys.next() {
-                    let key: Key = key.into();
-
-                    #(
-                        let #ref_storage_vars = self.store.get::<#ref_idents>(&key).unwrap_or_else(|| panic!("Supplied key has no {} fields", std::any::type_name::<#ref_idents>()));
-                    )*
-                    #(
-                        let #option_ref_storage_vars = self.store.get::<#option_ref_idents>(&key);
-                    )*
-                    #(
-                        let #mut_ref_storage_vars = self.store.get_mut::<#mut_ref_idents>(&key).unwrap_or_else(|| panic!("Supplied key has no {} fields", std::any::type_name::<#mut_ref_idents>()));
-                    )*
-                    #(
-                        let #option_mut_ref_storage_vars = self.store.get_mut::<#option_mut_ref_idents>(&key);
-                    )*
-
-                    Some((key #(, NoField::<#no_field_idents>::default())* #(, #ref_storage_vars)* #(, #option_ref_storage_vars)* #(, #mut_ref_storage_vars)* #(, #option_mut_ref_storage_vars)*))
-                } else {
-                    None
-                }
+                let (key, slot) = self.keys.next()?;
+                let key: Key = self.store.key_at(key.to_index());
+
+                #borrow_columns
+
+                #(
+                    let #ref_storage_vars = match (#ref_column_vars, row) {
+                        (Some(column), Some(row)) => {
+                            self.store.record_access::<#ref_idents>(StorageAccess::Iterated);
+                            column.get(row)
+                        }
+                        _ => self.store.get_iterated::<#ref_idents>(&key).unwrap_or_else(|| panic!("Supplied key has no {} fields", std::any::type_name::<#ref_idents>())),
+                    };
+                )*
+                #(
+                    let #option_ref_storage_vars = match (#option_ref_column_vars, row) {
+                        (Some(column), Some(row)) => {
+                            self.store.record_access::<#option_ref_idents>(StorageAccess::Iterated);
+                            Some(column.get(row))
+                        }
+                        (None, Some(_)) if holds_all_columns => None,
+                        _ => self.store.get_iterated::<#option_ref_idents>(&key),
+                    };
+                )*
+                #(
+                    let #mut_ref_storage_vars = match (#mut_ref_column_vars, row) {
+                        (Some(column), Some(row)) => {
+                            self.store.record_access::<#mut_ref_idents>(StorageAccess::Iterated);
+                            column.get_mut(row)
+                        }
+                        _ => self.store.get_mut_iterated::<#mut_ref_idents>(&key).unwrap_or_else(|| panic!("Supplied key has no {} fields", std::any::type_name::<#mut_ref_idents>())),
+                    };
+                )*
+                #(
+                    let #option_mut_ref_storage_vars = match (#option_mut_ref_column_vars, row) {
+                        (Some(column), Some(row)) => {
+                            self.store.record_access::<#option_mut_ref_idents>(StorageAccess::Iterated);
+                            Some(column.get_mut(row))
+                        }
+                        (None, Some(_)) if holds_all_columns => None,
+                        _ => self.store.get_mut_iterated::<#option_mut_ref_idents>(&key),
+                    };
+                )*
+
+                Some((key #(, NoField::<#no_field_idents>::default())* #(, #ref_storage_vars)* #(, #option_ref_storage_vars)* #(, #mut_ref_storage_vars)* #(, #option_mut_ref_storage_vars)*))
             }
         }
     )
@@ -0,0 +1,93 @@
+use proc_macro::TokenStream;
+use syn::{
+    export::quote::quote, parse_macro_input, Data, DeriveInput, Fields, Lit, LitStr, Meta,
+    NestedMeta,
+};
+
+pub fn derive_store_key(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let ident = &input.ident;
+
+    let inner = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Unnamed(fields) if fields.unnamed.len() == 1 => &fields.unnamed[0].ty,
+            _ => panic!("StoreKey can only be derived for single-field tuple structs"),
+        },
+        _ => panic!("StoreKey can only be derived for single-field tuple structs"),
+    };
+
+    let display = store_key_prefix(&input).map(|prefix| {
+        quote!(
+            impl std::fmt::Display for #ident {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    write!(f, "{}{}", #prefix, self.0)
+                }
+            }
+        )
+    });
+
+    quote!(
+        impl ::store::StoreKey for #ident {
+            type KeySet = <#inner as ::store::StoreKey>::KeySet;
+
+            const GENERATIONAL: bool = <#inner as ::store::StoreKey>::GENERATIONAL;
+
+            fn to_index(&self) -> u64 {
+                ::store::StoreKey::to_index(&self.0)
+            }
+
+            fn from_index(index: u64) -> Self {
+                #ident(<#inner as ::store::StoreKey>::from_index(index))
+            }
+
+            fn from_parts(index: u64, generation: u32) -> Self {
+                #ident(<#inner as ::store::StoreKey>::from_parts(index, generation))
+            }
+        }
+
+        impl From<#inner> for #ident {
+            fn from(key: #inner) -> Self {
+                #ident(key)
+            }
+        }
+
+        impl From<#ident> for #inner {
+            fn from(key: #ident) -> Self {
+                key.0
+            }
+        }
+
+        #display
+    )
+    .into()
+}
+
+/// The `prefix` of a `#[store_key(prefix = "...")]` attribute, if present
+fn store_key_prefix(input: &DeriveInput) -> Option<LitStr> {
+    let attr = input
+        .attrs
+        .iter()
+        .find(|attr| attr.path.is_ident("store_key"))?;
+
+    let mut prefix = None;
+    match attr.parse_meta() {
+        Ok(Meta::List(list)) => {
+            for meta in list.nested {
+                match meta {
+                    NestedMeta::Meta(Meta::NameValue(name_value))
+                        if name_value.path.is_ident("prefix") =>
+                    {
+                        match name_value.lit {
+                            Lit::Str(lit) => prefix = Some(lit),
+                            _ => panic!("store_key prefix must be a string literal"),
+                        }
+                    }
+                    _ => panic!("Unrecognized store_key attribute, expected prefix = \"...\""),
+                }
+            }
+        }
+        _ => panic!("Expected #[store_key(prefix = \"...\")]"),
+    }
+
+    prefix
+}